@@ -1,79 +1,99 @@
-use anyhow::{Result};
-use reqwest::{Client};
+use reqwest::Method;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-
-const DEFAULT_API_URL: &str = "http://127.0.0.1:9090";
+use super::controller::{self, Controller};
+use super::error::{ApiResult, ClashApiError};
+use super::transport::{ApiResponse, Transport};
 
 /// Clash API 客户端
 ///
 /// 用于与 Clash 外部控制 API 进行交互
 pub struct ClashClient {
-    client: Client,
-    base_url: String,
+    transport: Transport,
 }
 
 impl ClashClient {
     /// 创建一个新的 API 客户端实例
+    ///
+    /// 控制器地址和密钥来自命令行参数、环境变量或当前配置文件
     pub fn new() -> Self {
+        Self::with_controller(&controller::resolve())
+    }
+
+    /// 使用指定的控制器参数创建客户端 (TCP 或 Unix Socket)
+    pub fn with_controller(controller: &Controller) -> Self {
         Self {
-            client: Client::new(),
-            base_url: DEFAULT_API_URL.to_string(),
+            transport: Transport::new(controller),
         }
     }
 
+    /// 发送请求并检查状态码, `subject` 用于 404/超时错误提示
+    async fn send(&self, method: Method, path: &str, body: Option<&serde_json::Value>, subject: &str) -> ApiResult<ApiResponse> {
+        self.transport.request(method, path, body).await?
+            .error_for_status(subject).await
+    }
+
+    /// 发送 GET 请求
+    async fn get(&self, path: &str) -> ApiResult<ApiResponse> {
+        self.send(Method::GET, path, None, path).await
+    }
+
     /// 获取所有代理组和节点信息
-    pub async fn get_proxies(&self) -> Result<HashMap<String, ProxyItem>> {
-        let url = format!("{}/proxies", self.base_url);
-        let resp: ProxiesResponse = self.client.get(&url).send().await?.json().await?;
+    pub async fn get_proxies(&self) -> ApiResult<HashMap<String, ProxyItem>> {
+        let resp: ProxiesResponse = self.get("/proxies").await?.json().await?;
         Ok(resp.proxies)
     }
 
     /// 切换指定代理组的选中节点
-    pub async fn select_proxy(&self, group_name: &str, proxy_name: &str) -> Result<()> {
-        let url = format!("{}/proxies/{}", self.base_url, group_name);
+    pub async fn select_proxy(&self, group_name: &str, proxy_name: &str) -> ApiResult<()> {
+        let path = format!("/proxies/{}", urlencoding::encode(group_name));
         let payload = serde_json::json!({ "name": proxy_name });
-        let resp = self.client.put(&url).json(&payload).send().await?;
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Failed to select proxy: {}", resp.status()))
-        }
+        self.send(Method::PUT, &path, Some(&payload), group_name).await?;
+        Ok(())
     }
     
     /// 获取当前配置信息
-    pub async fn get_config(&self) -> Result<Config> {
-        let url = format!("{}/configs", self.base_url);
-        let config: Config = self.client.get(&url).send().await?.json().await?;
+    pub async fn get_config(&self) -> ApiResult<Config> {
+        let config: Config = self.get("/configs").await?.json().await?;
         Ok(config)
     }
 
     /// 更新 Clash 配置 (如切换模式)
-    pub async fn update_config(&self, payload: &serde_json::Value) -> Result<()> {
-        let url = format!("{}/configs", self.base_url);
-        let resp = self.client.patch(&url).json(payload).send().await?;
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Failed to update config: {}", resp.status()))
-        }
+    pub async fn update_config(&self, payload: &serde_json::Value) -> ApiResult<()> {
+        self.send(Method::PATCH, "/configs", Some(payload), "/configs").await?;
+        Ok(())
     }
     
+    /// 让 Clash 重新加载配置文件, 不中断服务进程
+    ///
+    /// 对应 `PUT /configs?force=true`, `path` 为空时重新加载启动时的配置文件
+    pub async fn reload_config(&self, path: &str) -> ApiResult<()> {
+        let payload = serde_json::json!({ "path": path, "payload": "" });
+        self.send(Method::PUT, "/configs?force=true", Some(&payload), "/configs").await?;
+        Ok(())
+    }
+
     /// 测试指定节点的延迟
-    pub async fn delay_test(&self, proxy_name: &str) -> Result<u64> {
-         // URL encoded proxy name? reqwest handles path segments but if it has slash?
-         // Assuming proxy name is safe or simple.
-         // Actually, delay test usually works on specific nodes, not groups.
-         // But user wants "Check current node status".
-         let url = format!("{}/proxies/{}/delay?timeout=5000&url=http://www.gstatic.com/generate_204", self.base_url, urlencoding::encode(proxy_name));
-         let resp: DelayResponse = self.client.get(&url).send().await?.json().await?;
+    pub async fn delay_test(&self, proxy_name: &str, opts: &DelayTest) -> ApiResult<u64> {
+         // 节点名可能包含空格、斜杠或 emoji, 需要进行 URL 编码
+         let path = format!("/proxies/{}/delay?{}", urlencoding::encode(proxy_name), opts.query());
+         let resp: DelayResponse = self.send(Method::GET, &path, None, proxy_name).await?.json().await?;
          Ok(resp.delay)
     }
 
+    /// 使用 Mihomo 的组测速接口测试代理组内所有节点
+    ///
+    /// 返回测试成功的节点及其延迟, 失败的节点不包含在结果中
+    pub async fn group_delay_test(&self, group_name: &str, opts: &DelayTest) -> ApiResult<HashMap<String, u64>> {
+        let path = format!("/group/{}/delay?{}", urlencoding::encode(group_name), opts.query());
+        let resp: HashMap<String, u64> = self.send(Method::GET, &path, None, group_name).await?.json().await?;
+        Ok(resp)
+    }
+
     /// 获取当前流量信息 (快照)
-    pub async fn get_traffic(&self) -> Result<Traffic> {
-        let url = format!("{}/traffic", self.base_url);
-        let mut response = self.client.get(&url).send().await?;
+    pub async fn get_traffic(&self) -> ApiResult<Traffic> {
+        let mut response = self.get("/traffic").await?;
         
         // 读取第一个数据包
         if let Some(chunk) = response.chunk().await? {
@@ -85,33 +105,47 @@ impl ClashClient {
                 return Ok(traffic);
             }
         }
-        Err(anyhow::anyhow!("No traffic data received"))
+        Err(ClashApiError::Transport("未收到流量数据".to_string()))
     }
 
     /// 获取版本信息
-    pub async fn get_version(&self) -> Result<Version> {
-        let url = format!("{}/version", self.base_url);
-        let version: Version = self.client.get(&url).send().await?.json().await?;
+    pub async fn get_version(&self) -> ApiResult<Version> {
+        let version: Version = self.get("/version").await?.json().await?;
         Ok(version)
     }
 
     /// 获取当前活跃连接数
-    pub async fn get_connection_count(&self) -> Result<usize> {
-        let url = format!("{}/connections", self.base_url);
-        let resp: ConnectionsResponse = self.client.get(&url).send().await?.json().await?;
-        Ok(resp.connections.len())
+    pub async fn get_connection_count(&self) -> ApiResult<usize> {
+        Ok(self.get_connections().await?.connections.len())
+    }
+
+    /// 获取所有活跃连接及累计流量
+    pub async fn get_connections(&self) -> ApiResult<Connections> {
+        let resp: Connections = self.get("/connections").await?.json().await?;
+        Ok(resp)
+    }
+
+    /// 关闭指定连接
+    pub async fn close_connection(&self, id: &str) -> ApiResult<()> {
+        let path = format!("/connections/{}", urlencoding::encode(id));
+        self.send(Method::DELETE, &path, None, id).await?;
+        Ok(())
+    }
+
+    /// 关闭所有连接
+    pub async fn close_all_connections(&self) -> ApiResult<()> {
+        self.send(Method::DELETE, "/connections", None, "/connections").await?;
+        Ok(())
     }
 
     /// 获取流量流 (Streaming Response)
-    pub async fn stream_traffic(&self) -> Result<reqwest::Response> {
-        let url = format!("{}/traffic", self.base_url);
-        Ok(self.client.get(&url).send().await?)
+    pub async fn stream_traffic(&self) -> ApiResult<ApiResponse> {
+        self.get("/traffic").await
     }
 
     /// 获取日志流 (Streaming Response)
-    pub async fn stream_logs(&self) -> Result<reqwest::Response> {
-        let url = format!("{}/logs?level=info", self.base_url);
-        Ok(self.client.get(&url).send().await?)
+    pub async fn stream_logs(&self) -> ApiResult<ApiResponse> {
+        self.get("/logs?level=info").await
     }
 }
 
@@ -120,9 +154,102 @@ pub struct Version {
     pub version: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct ConnectionsResponse {
-    connections: Vec<serde_json::Value>,
+/// /connections 接口返回的连接快照
+#[derive(Debug, Deserialize, Clone)]
+pub struct Connections {
+    /// 累计下载字节数
+    #[serde(rename = "downloadTotal", default)]
+    pub download_total: u64,
+    /// 累计上传字节数
+    #[serde(rename = "uploadTotal", default)]
+    pub upload_total: u64,
+    /// 活跃连接列表 (没有连接时部分内核返回 null)
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub connections: Vec<Connection>,
+}
+
+/// 单个活跃连接
+#[derive(Debug, Deserialize, Clone)]
+pub struct Connection {
+    pub id: String,
+    pub metadata: ConnectionMetadata,
+    /// 已上传字节数
+    #[serde(default)]
+    pub upload: u64,
+    /// 已下载字节数
+    #[serde(default)]
+    pub download: u64,
+    /// 连接建立时间
+    pub start: DateTime<Utc>,
+    /// 代理链 (从最终节点到策略组的顺序)
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub chains: Vec<String>,
+    /// 命中的规则类型
+    #[serde(default)]
+    pub rule: String,
+    /// 规则参数
+    #[serde(rename = "rulePayload", default)]
+    pub rule_payload: String,
+}
+
+/// 连接元数据
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectionMetadata {
+    /// tcp / udp
+    pub network: String,
+    /// 入站类型 (HTTP, Socks5, Tun 等)
+    #[serde(rename = "type")]
+    pub conn_type: String,
+    #[serde(rename = "sourceIP")]
+    pub source_ip: String,
+    pub source_port: String,
+    #[serde(rename = "destinationIP")]
+    pub destination_ip: String,
+    pub destination_port: String,
+    pub host: String,
+    /// 发起连接的进程路径 (需开启 find-process-mode)
+    pub process_path: String,
+}
+
+impl Connection {
+    /// 目标地址, 优先使用域名
+    pub fn destination(&self) -> String {
+        let host = if self.metadata.host.is_empty() {
+            &self.metadata.destination_ip
+        } else {
+            &self.metadata.host
+        };
+        format!("{}:{}", host, self.metadata.destination_port)
+    }
+
+    /// 代理链展示形式, 如 "Proxy > 香港 01"
+    pub fn chain_display(&self) -> String {
+        self.chains.iter().rev().cloned().collect::<Vec<_>>().join(" > ")
+    }
+
+    /// 规则展示形式, 如 "DomainSuffix(google.com)"
+    pub fn rule_display(&self) -> String {
+        if self.rule_payload.is_empty() {
+            self.rule.clone()
+        } else {
+            format!("{}({})", self.rule, self.rule_payload)
+        }
+    }
+
+    /// 进程名 (取进程路径的最后一段)
+    pub fn process_name(&self) -> &str {
+        self.metadata.process_path.rsplit('/').next().unwrap_or("")
+    }
+}
+
+/// 将 JSON 中的 null 视为默认值
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 #[derive(Debug, Deserialize)]
@@ -139,8 +266,6 @@ struct ProxiesResponse {
 /// 代理节点/组信息
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProxyItem {
-    /// 代理名称
-    pub name: String,
     /// 代理类型 (Selector, URLTest, Direct, etc.)
     #[serde(rename = "type")]
     pub proxy_type: String,
@@ -158,6 +283,36 @@ pub struct History {
     pub delay: u64,
 }
 
+/// 默认的延迟测试地址
+pub const DEFAULT_TEST_URL: &str = "http://www.gstatic.com/generate_204";
+
+/// 默认的延迟测试超时 (毫秒)
+pub const DEFAULT_TEST_TIMEOUT: u64 = 5000;
+
+/// 延迟测试参数
+#[derive(Debug, Clone)]
+pub struct DelayTest {
+    /// 测试地址
+    pub url: String,
+    /// 超时时间 (毫秒)
+    pub timeout_ms: u64,
+}
+
+impl Default for DelayTest {
+    fn default() -> Self {
+        Self {
+            url: DEFAULT_TEST_URL.to_string(),
+            timeout_ms: DEFAULT_TEST_TIMEOUT,
+        }
+    }
+}
+
+impl DelayTest {
+    fn query(&self) -> String {
+        format!("timeout={}&url={}", self.timeout_ms, urlencoding::encode(&self.url))
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct DelayResponse {
     delay: u64,
@@ -0,0 +1,141 @@
+use once_cell::sync::OnceCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 当前激活的 Clash 配置文件
+pub const ACTIVE_CONFIG_FILE: &str = "/etc/clash/config.yaml";
+
+/// Clash 工作目录 (相对路径的 Unix Socket 以此为基准)
+const CLASH_HOME: &str = "/etc/clash";
+
+const DEFAULT_CONTROLLER: &str = "127.0.0.1:9090";
+
+/// 命令行 / 环境变量指定的覆盖参数 (由 main 在启动时设置)
+static OVERRIDES: OnceCell<Overrides> = OnceCell::new();
+
+#[derive(Debug, Default)]
+struct Overrides {
+    api_url: Option<String>,
+    api_socket: Option<PathBuf>,
+    secret: Option<String>,
+}
+
+/// 控制器监听端点
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    /// TCP 地址, 如 http://127.0.0.1:9090
+    Tcp(String),
+    /// Unix Socket 路径 (对应配置中的 `external-controller-unix`)
+    Unix(PathBuf),
+}
+
+/// 外部控制器连接参数
+#[derive(Debug, Clone)]
+pub struct Controller {
+    /// API 端点
+    pub endpoint: Endpoint,
+    /// 鉴权密钥 (对应配置中的 `secret`)
+    pub secret: Option<String>,
+}
+
+/// 设置全局覆盖参数
+///
+/// 应在创建任何 `ClashClient` 之前调用, 重复调用将被忽略
+pub fn init(api_url: Option<String>, api_socket: Option<PathBuf>, secret: Option<String>) {
+    let _ = OVERRIDES.set(Overrides { api_url, api_socket, secret });
+}
+
+/// 解析控制器连接参数
+///
+/// 优先级: 命令行参数 / 环境变量 > 当前配置文件 > 默认值。
+/// 配置文件中只开启了 `external-controller-unix` 时使用 Unix Socket。
+pub fn resolve() -> Controller {
+    let overrides = OVERRIDES.get_or_init(Overrides::default);
+    let doc = read_active_config();
+    let config_str = |key: &str| doc.as_ref()
+        .and_then(|d| d.get(key))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    let endpoint = if let Some(url) = &overrides.api_url {
+        Endpoint::Tcp(normalize_address(url))
+    } else if let Some(socket) = &overrides.api_socket {
+        Endpoint::Unix(socket.clone())
+    } else {
+        match (config_str("external-controller"), config_str("external-controller-unix")) {
+            (None, Some(socket)) => Endpoint::Unix(Path::new(CLASH_HOME).join(socket)),
+            (address, _) => Endpoint::Tcp(normalize_address(address.as_deref().unwrap_or(DEFAULT_CONTROLLER))),
+        }
+    };
+
+    let secret = overrides.secret.clone()
+        .or_else(|| doc.as_ref()
+            .and_then(|d| d.get("secret"))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()))
+        .filter(|s| !s.is_empty());
+
+    Controller { endpoint, secret }
+}
+
+/// 读取当前配置文件 (不使用 sudo, 读取失败时返回 None)
+fn read_active_config() -> Option<serde_yaml::Value> {
+    let content = fs::read_to_string(ACTIVE_CONFIG_FILE).ok()?;
+    serde_yaml::from_str(&content).ok()
+}
+
+/// 将 `external-controller` 形式的地址转换为可访问的 URL
+///
+/// 处理 `:9090`、`0.0.0.0:9090`、`[::]:9090` 等监听地址写法, 不带端口的 IPv6 地址 (如 `::1`) 使用默认端口
+fn normalize_address(address: &str) -> String {
+    let address = address.trim().trim_end_matches('/');
+    if address.starts_with("http://") || address.starts_with("https://") {
+        return address.to_string();
+    }
+
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        // [IPv6]:port
+        match rest.split_once(']') {
+            Some((host, tail)) => (host, tail.strip_prefix(':').filter(|p| !p.is_empty()).unwrap_or("9090")),
+            None => (rest, "9090"),
+        }
+    } else if address.matches(':').count() > 1 {
+        // 不带方括号的 IPv6 地址不能再附加端口
+        (address, "9090")
+    } else {
+        address.rsplit_once(':').unwrap_or((address, "9090"))
+    };
+    match host {
+        "" | "0.0.0.0" => format!("http://127.0.0.1:{}", port),
+        "::" => format!("http://[::1]:{}", port),
+        h if h.contains(':') => format!("http://[{}]:{}", h, port),
+        h => format!("http://{}:{}", h, port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_controller_addresses() {
+        let cases = [
+            ("127.0.0.1:9090", "http://127.0.0.1:9090"),
+            (":9090", "http://127.0.0.1:9090"),
+            ("0.0.0.0:9097", "http://127.0.0.1:9097"),
+            ("localhost", "http://localhost:9090"),
+            ("[::]:9090", "http://[::1]:9090"),
+            ("[::1]:9091", "http://[::1]:9091"),
+            ("[fe80::1]", "http://[fe80::1]:9090"),
+            ("::", "http://[::1]:9090"),
+            ("::1", "http://[::1]:9090"),
+            ("2001:db8::1", "http://[2001:db8::1]:9090"),
+            ("http://10.0.0.1:9090/", "http://10.0.0.1:9090"),
+            (" https://clash.example.com ", "https://clash.example.com"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_address(input), expected, "{}", input);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use serde_yaml::Value;
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+use std::collections::BTreeMap;
+
+/// 参与比较的顶层设置
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "mixed-port", "port", "socks-port", "redir-port", "tproxy-port",
+    "mode", "allow-lan", "bind-address", "ipv6", "log-level",
+    "external-controller", "external-controller-unix", "secret",
+];
+
+/// 单条差异
+pub enum Change {
+    Added(String),
+    Removed(String),
+    Modified(String),
+}
+
+/// 一组差异, 如 "节点"、"规则"
+pub struct Section {
+    pub title: &'static str,
+    pub changes: Vec<Change>,
+}
+
+/// 比较两份配置, 返回有差异的分组
+pub fn diff_configs(old: &Value, new: &Value) -> Vec<Section> {
+    let sections = vec![
+        Section { title: "基本设置", changes: diff_top_level(old, new) },
+        Section { title: "节点", changes: diff_proxies(old, new) },
+        Section { title: "代理组", changes: diff_groups(old, new) },
+        Section { title: "规则", changes: diff_rules(old, new) },
+        Section { title: "DNS", changes: diff_flattened(old.get("dns"), new.get("dns")) },
+        Section { title: "TUN", changes: diff_flattened(old.get("tun"), new.get("tun")) },
+    ];
+    sections.into_iter().filter(|s| !s.changes.is_empty()).collect()
+}
+
+/// 将值格式化为单行文本
+fn compact(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "null".to_string(),
+        other => serde_json::to_string(other)
+            .unwrap_or_else(|_| serde_yaml::to_string(other).unwrap_or_default().trim().to_string()),
+    }
+}
+
+/// 只报告有变化、不显示值的设置
+const MASKED_KEYS: &[&str] = &["secret"];
+
+fn diff_top_level(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for key in TOP_LEVEL_KEYS {
+        let show = |v: &Value| if MASKED_KEYS.contains(key) { "******".to_string() } else { compact(v) };
+        match (old.get(*key), new.get(*key)) {
+            (None, Some(v)) => changes.push(Change::Added(format!("{}: {}", key, show(v)))),
+            (Some(v), None) => changes.push(Change::Removed(format!("{}: {}", key, show(v)))),
+            (Some(a), Some(b)) if a != b => {
+                let detail = if MASKED_KEYS.contains(key) {
+                    "已修改".to_string()
+                } else {
+                    format!("{} → {}", compact(a), compact(b))
+                };
+                changes.push(Change::Modified(format!("{}: {}", key, detail)))
+            }
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// 按 name 索引列表项, 保留原有顺序
+fn by_name(doc: &Value, key: &str) -> Vec<(String, Value)> {
+    doc.get(key)
+        .and_then(|v| v.as_sequence())
+        .map(|items| items.iter()
+            .filter_map(|item| {
+                let name = item.get("name")?.as_str()?.to_string();
+                Some((name, item.clone()))
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+fn find<'a>(items: &'a [(String, Value)], name: &str) -> Option<&'a Value> {
+    items.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+}
+
+/// 节点的简要描述: 类型和服务器地址
+fn describe_proxy(proxy: &Value) -> String {
+    let field = |key: &str| proxy.get(key).map(compact).unwrap_or_default();
+    format!("{}, {}:{}", field("type"), field("server"), field("port"))
+}
+
+fn diff_proxies(old: &Value, new: &Value) -> Vec<Change> {
+    let (old, new) = (by_name(old, "proxies"), by_name(new, "proxies"));
+    let mut changes = Vec::new();
+    for (name, proxy) in &new {
+        match find(&old, name) {
+            None => changes.push(Change::Added(format!("{} ({})", name, describe_proxy(proxy)))),
+            Some(prev) if prev != proxy => {
+                let (before, after) = (describe_proxy(prev), describe_proxy(proxy));
+                let detail = if before != after {
+                    format!("{} → {}", before, after)
+                } else {
+                    // 服务器相同, 列出改动的字段
+                    let keys = diff_flattened(Some(prev), Some(proxy)).into_iter()
+                        .filter_map(|c| match c {
+                            Change::Added(l) | Change::Removed(l) | Change::Modified(l) => {
+                                l.split(':').next().map(|k| k.to_string())
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    format!("{} 已修改", keys.join(", "))
+                };
+                changes.push(Change::Modified(format!("{}: {}", name, detail)));
+            }
+            _ => {}
+        }
+    }
+    for (name, proxy) in &old {
+        if find(&new, name).is_none() {
+            changes.push(Change::Removed(format!("{} ({})", name, describe_proxy(proxy))));
+        }
+    }
+    changes
+}
+
+fn members(group: &Value) -> Vec<String> {
+    group.get("proxies")
+        .and_then(|v| v.as_sequence())
+        .map(|m| m.iter().map(compact).collect())
+        .unwrap_or_default()
+}
+
+fn diff_groups(old: &Value, new: &Value) -> Vec<Change> {
+    let (old, new) = (by_name(old, "proxy-groups"), by_name(new, "proxy-groups"));
+    let mut changes = Vec::new();
+    for (name, group) in &new {
+        let kind = group.get("type").map(compact).unwrap_or_default();
+        let Some(prev) = find(&old, name) else {
+            changes.push(Change::Added(format!("{} ({}, {} 个成员)", name, kind, members(group).len())));
+            continue;
+        };
+        if prev == group {
+            continue;
+        }
+
+        let mut details = Vec::new();
+        let prev_kind = prev.get("type").map(compact).unwrap_or_default();
+        if prev_kind != kind {
+            details.push(format!("类型 {} → {}", prev_kind, kind));
+        }
+        let (before, after) = (members(prev), members(group));
+        let added: Vec<&String> = after.iter().filter(|m| !before.contains(m)).collect();
+        let removed: Vec<&String> = before.iter().filter(|m| !after.contains(m)).collect();
+        if !added.is_empty() {
+            details.push(format!("新增成员 {}", added.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+        if !removed.is_empty() {
+            details.push(format!("移除成员 {}", removed.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+        if added.is_empty() && removed.is_empty() && before != after {
+            details.push("成员顺序变化".to_string());
+        }
+        if details.is_empty() {
+            details.push("参数已修改".to_string());
+        }
+        changes.push(Change::Modified(format!("{}: {}", name, details.join("; "))));
+    }
+    for (name, group) in &old {
+        if find(&new, name).is_none() {
+            let kind = group.get("type").map(compact).unwrap_or_default();
+            changes.push(Change::Removed(format!("{} ({})", name, kind)));
+        }
+    }
+    changes
+}
+
+/// 按顺序比较规则, 行号为规则在各自配置中的位置 (从 1 开始)
+fn diff_rules(old: &Value, new: &Value) -> Vec<Change> {
+    let rules = |doc: &Value| -> Vec<String> {
+        doc.get("rules")
+            .and_then(|v| v.as_sequence())
+            .map(|r| r.iter().map(compact).collect())
+            .unwrap_or_default()
+    };
+    let (old, new) = (rules(old), rules(new));
+    let mut changes = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, &old, &new) {
+        // Replace 同时包含删除和插入两部分
+        if matches!(op.tag(), DiffTag::Delete | DiffTag::Replace) {
+            let range = op.old_range();
+            for (i, rule) in old.iter().enumerate().skip(range.start).take(range.len()) {
+                changes.push(Change::Removed(format!("#{} {}", i + 1, rule)));
+            }
+        }
+        if matches!(op.tag(), DiffTag::Insert | DiffTag::Replace) {
+            let range = op.new_range();
+            for (i, rule) in new.iter().enumerate().skip(range.start).take(range.len()) {
+                changes.push(Change::Added(format!("#{} {}", i + 1, rule)));
+            }
+        }
+    }
+    changes
+}
+
+/// 展开嵌套映射为 "a.b.c" 形式的键
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Mapping(map) if !map.is_empty() => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() { compact(k) } else { format!("{}.{}", prefix, compact(k)) };
+                flatten(&key, v, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), compact(other));
+        }
+    }
+}
+
+/// 比较两个 (可能缺失的) 映射的所有叶子字段
+fn diff_flattened(old: Option<&Value>, new: Option<&Value>) -> Vec<Change> {
+    let (mut a, mut b) = (BTreeMap::new(), BTreeMap::new());
+    if let Some(v) = old {
+        flatten("", v, &mut a);
+    }
+    if let Some(v) = new {
+        flatten("", v, &mut b);
+    }
+    let mut changes = Vec::new();
+    for (key, value) in &b {
+        match a.get(key) {
+            None => changes.push(Change::Added(format!("{}: {}", key, value))),
+            Some(prev) if prev != value => changes.push(Change::Modified(format!("{}: {} → {}", key, prev, value))),
+            _ => {}
+        }
+    }
+    for (key, value) in &a {
+        if !b.contains_key(key) {
+            changes.push(Change::Removed(format!("{}: {}", key, value)));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 将差异展开为 "分组 符号 内容" 形式的行, 便于断言
+    fn render(old: &str, new: &str) -> Vec<String> {
+        let old: Value = serde_yaml::from_str(old).unwrap();
+        let new: Value = serde_yaml::from_str(new).unwrap();
+        diff_configs(&old, &new).into_iter()
+            .flat_map(|section| section.changes.into_iter().map(move |change| match change {
+                Change::Added(line) => format!("{} + {}", section.title, line),
+                Change::Removed(line) => format!("{} - {}", section.title, line),
+                Change::Modified(line) => format!("{} ~ {}", section.title, line),
+            }))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_sections() {
+        let cases: [(&str, &str, &[&str]); 7] = [
+            ("mode: rule\nmixed-port: 7890", "mode: rule\nmixed-port: 7890", &[]),
+            (
+                "mode: rule\nallow-lan: true",
+                "mode: global\nlog-level: info",
+                &["基本设置 ~ mode: rule → global", "基本设置 - allow-lan: true", "基本设置 + log-level: info"],
+            ),
+            (
+                "proxies: [{name: a, type: ss, server: 1.1.1.1, port: 1}, {name: b, type: ss, server: b.com, port: 2}]",
+                "proxies: [{name: a, type: ss, server: 2.2.2.2, port: 1}, {name: c, type: vmess, server: c.com, port: 3}]",
+                &[
+                    "节点 ~ a: ss, 1.1.1.1:1 → ss, 2.2.2.2:1",
+                    "节点 + c (vmess, c.com:3)",
+                    "节点 - b (ss, b.com:2)",
+                ],
+            ),
+            (
+                "proxies: [{name: a, type: ss, server: s, port: 1, password: x, udp: true}]",
+                "proxies: [{name: a, type: ss, server: s, port: 1, password: y}]",
+                &["节点 ~ a: password, udp 已修改"],
+            ),
+            (
+                "proxy-groups: [{name: P, type: select, proxies: [a, b]}, {name: Q, type: select, proxies: [a, b]}, {name: R, type: fallback}]",
+                "proxy-groups: [{name: P, type: url-test, proxies: [a, c]}, {name: Q, type: select, proxies: [b, a]}, {name: S, type: select, proxies: [a]}]",
+                &[
+                    "代理组 ~ P: 类型 select → url-test; 新增成员 c; 移除成员 b",
+                    "代理组 ~ Q: 成员顺序变化",
+                    "代理组 + S (select, 1 个成员)",
+                    "代理组 - R (fallback)",
+                ],
+            ),
+            (
+                "rules: ['DOMAIN,a.com,P', 'DOMAIN,b.com,P', 'MATCH,P']",
+                "rules: ['DOMAIN,a.com,P', 'DOMAIN,c.com,DIRECT', 'MATCH,P']",
+                &["规则 - #2 DOMAIN,b.com,P", "规则 + #2 DOMAIN,c.com,DIRECT"],
+            ),
+            (
+                "dns: {enable: true, nameserver: [1.1.1.1]}",
+                "dns: {enable: false, fallback-filter: {geoip: true}, nameserver: [1.1.1.1]}\ntun: {enable: true}",
+                &[
+                    "DNS ~ enable: true → false",
+                    "DNS + fallback-filter.geoip: true",
+                    "TUN + enable: true",
+                ],
+            ),
+        ];
+        for (old, new, expected) in cases {
+            assert_eq!(render(old, new), expected, "{} => {}", old, new);
+        }
+    }
+
+    #[test]
+    fn masks_secret() {
+        let cases: [(&str, &str, &[&str]); 3] = [
+            ("secret: old", "secret: new", &["基本设置 ~ secret: 已修改"]),
+            ("mode: rule", "mode: rule\nsecret: new", &["基本设置 + secret: ******"]),
+            ("secret: old", "{}", &["基本设置 - secret: ******"]),
+        ];
+        for (old, new, expected) in cases {
+            assert_eq!(render(old, new), expected, "{} => {}", old, new);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use thiserror::Error;
+
+/// Clash API 调用结果
+pub type ApiResult<T> = std::result::Result<T, ClashApiError>;
+
+/// Clash 控制器 API 错误
+#[derive(Debug, Error)]
+pub enum ClashApiError {
+    /// 无法建立连接 (服务未运行、端口/Socket 错误或无权限)
+    #[error("无法连接到 Clash 控制器 {endpoint}: {reason}")]
+    ConnectionRefused { endpoint: String, reason: String },
+
+    /// 控制器返回 401, 密钥错误或缺失
+    #[error("Clash 控制器拒绝访问 (401 Unauthorized)")]
+    Unauthorized,
+
+    /// 控制器返回 404, 代理或代理组不存在
+    #[error("未找到代理或代理组: {0}")]
+    NotFound(String),
+
+    /// 请求或延迟测试超时
+    #[error("{0} 超时")]
+    Timeout(String),
+
+    /// 组测速接口没有返回该节点的结果 (超时或测试出错, 接口不区分原因)
+    #[error("{0} 没有测速结果")]
+    NoResult(String),
+
+    /// 响应不是预期的 JSON 结构
+    #[error("无法解析控制器返回的 JSON: {0}")]
+    MalformedJson(#[from] serde_json::Error),
+
+    /// 其他非成功状态码
+    #[error("控制器返回错误 {status}: {message}")]
+    Status { status: u16, message: String },
+
+    /// 其他传输层错误
+    #[error("请求 Clash 控制器失败: {0}")]
+    Transport(String),
+}
+
+impl ClashApiError {
+    /// 针对错误类型给出的处理建议
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ClashApiError::ConnectionRefused { .. } => "请确认 Clash 正在运行 (clash-cli proxy start)，并检查 --api-url / --api-socket 或配置中的 external-controller。",
+            ClashApiError::Unauthorized => "请通过 --secret 或 CLASH_SECRET 提供与配置中 secret 一致的密钥。",
+            ClashApiError::NotFound(_) => "请使用 clash-cli status 查看可用的代理组和节点名称。",
+            ClashApiError::Timeout(_) => "节点可能不可用，或网络较慢；可以稍后重试或切换节点。",
+            ClashApiError::NoResult(_) => "可以去掉 --group-api 逐个测试节点，查看具体的失败原因。",
+            ClashApiError::MalformedJson(_) => "控制器地址可能指向了其他服务，或内核版本与 clash-cli 不兼容。",
+            ClashApiError::Status { .. } => "请查看 Clash 日志 (journalctl -u clash) 获取详细信息。",
+            ClashApiError::Transport(_) => "请检查网络连接和控制器地址。",
+        }
+    }
+
+    /// 进程退出码, 便于脚本区分失败原因
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ClashApiError::ConnectionRefused { .. } => 3,
+            ClashApiError::Unauthorized => 4,
+            ClashApiError::NotFound(_) => 5,
+            ClashApiError::Timeout(_) => 6,
+            ClashApiError::MalformedJson(_) => 7,
+            ClashApiError::NoResult(_) | ClashApiError::Status { .. } | ClashApiError::Transport(_) => 8,
+        }
+    }
+
+    /// 用于行内展示的简短描述
+    pub fn short(&self) -> &'static str {
+        match self {
+            ClashApiError::ConnectionRefused { .. } => "无法连接",
+            ClashApiError::Unauthorized => "密钥错误",
+            ClashApiError::NotFound(_) => "不存在",
+            ClashApiError::Timeout(_) => "超时",
+            ClashApiError::NoResult(_) => "无结果",
+            ClashApiError::MalformedJson(_) => "响应无效",
+            ClashApiError::Status { .. } => "请求失败",
+            ClashApiError::Transport(_) => "网络错误",
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClashApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ClashApiError::Timeout("请求".to_string())
+        } else {
+            ClashApiError::Transport(e.to_string())
+        }
+    }
+}
+
+impl From<hyper::Error> for ClashApiError {
+    fn from(e: hyper::Error) -> Self {
+        if e.is_timeout() {
+            ClashApiError::Timeout("请求".to_string())
+        } else {
+            ClashApiError::Transport(e.to_string())
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use anyhow::{Result, anyhow};
+use colored::*;
+use serde_yaml::{Mapping, Value};
+
+/// 本地覆盖文件, 生成 config.yaml 时合并到任意配置之上
+pub const OVERRIDE_FILE: &str = "/etc/clash/override.yaml";
+
+/// 覆盖文件中的特殊键, 其余键按深度合并处理
+const PREPEND_RULES: &str = "prepend-rules";
+const APPEND_RULES: &str = "append-rules";
+const PREPEND_PROXIES: &str = "prepend-proxies";
+const APPEND_PROXIES: &str = "append-proxies";
+const GROUP_MEMBERS: &str = "proxy-group-members";
+
+/// 新建覆盖文件时使用的模板
+pub const OVERRIDE_TEMPLATE: &str = r#"# clash-cli 本地覆盖配置, 切换配置时合并到订阅之上
+#
+# 普通键按深度合并: 映射逐层合并, 其他值直接替换订阅中的值
+# mixed-port: 7890
+# allow-lan: true
+# secret: "my-secret"
+# dns:
+#   enable: true
+#
+# 规则: prepend 插入到最前, append 插入到最后的 MATCH 规则之前
+# prepend-rules:
+#   - DOMAIN-SUFFIX,internal.example.com,DIRECT
+# append-rules: []
+#
+# 节点: 与订阅中同名的节点会被替换
+# prepend-proxies: []
+# append-proxies: []
+#
+# 代理组成员
+# proxy-group-members:
+#   Proxy:
+#     prepend: [my-node]
+#     append: []
+"#;
+
+/// 解析覆盖文件内容并检查特殊键的类型
+///
+/// 空文件或只有注释时返回 None
+pub fn parse_override(content: &str) -> Result<Option<Mapping>> {
+    let value: Value = serde_yaml::from_str(content)?;
+    let map = match value {
+        Value::Null => return Ok(None),
+        Value::Mapping(map) if map.is_empty() => return Ok(None),
+        Value::Mapping(map) => map,
+        _ => return Err(anyhow!("覆盖文件顶层必须是映射")),
+    };
+
+    for key in [PREPEND_RULES, APPEND_RULES, PREPEND_PROXIES, APPEND_PROXIES] {
+        if let Some(v) = map.get(key) {
+            if !v.is_sequence() {
+                return Err(anyhow!("{} 必须是列表", key));
+            }
+        }
+    }
+    if let Some(groups) = map.get(GROUP_MEMBERS) {
+        let groups = groups.as_mapping().ok_or_else(|| anyhow!("{} 必须是映射", GROUP_MEMBERS))?;
+        for (group, ops) in groups {
+            let group = group.as_str().unwrap_or_default();
+            let ops = ops.as_mapping().ok_or_else(|| anyhow!("{}.{} 必须是映射", GROUP_MEMBERS, group))?;
+            for (op, members) in ops {
+                match op.as_str() {
+                    Some("prepend") | Some("append") if members.is_sequence() => {}
+                    Some("prepend") | Some("append") => {
+                        return Err(anyhow!("{}.{}.{} 必须是列表", GROUP_MEMBERS, group, op.as_str().unwrap_or_default()));
+                    }
+                    _ => return Err(anyhow!("{}.{} 只支持 prepend 和 append", GROUP_MEMBERS, group)),
+                }
+            }
+        }
+    }
+    Ok(Some(map))
+}
+
+/// 将覆盖内容应用到配置上
+///
+/// 订阅更新后可能不再包含覆盖文件引用的代理组, 这类条目只给出警告并跳过
+pub fn apply_override(config: &mut Value, overrides: &Mapping) -> Result<()> {
+    let doc = config.as_mapping_mut().ok_or_else(|| anyhow!("配置文件顶层必须是映射"))?;
+
+    for (key, value) in overrides {
+        match key.as_str() {
+            Some(PREPEND_RULES | APPEND_RULES | PREPEND_PROXIES | APPEND_PROXIES | GROUP_MEMBERS) => {}
+            _ => merge_value(doc, key.clone(), value.clone()),
+        }
+    }
+
+    let list = |key: &str| overrides.get(key).and_then(|v| v.as_sequence()).cloned().unwrap_or_default();
+
+    let (prepend, append) = (list(PREPEND_RULES), list(APPEND_RULES));
+    if !prepend.is_empty() || !append.is_empty() {
+        let rules = sequence_mut(doc, "rules");
+        rules.splice(0..0, prepend);
+        // MATCH 规则之后的规则永远不会命中, append 的规则插入到它之前
+        let match_at = rules.iter().rposition(|r| {
+            r.as_str().map(|r| r.trim_start().starts_with("MATCH,")).unwrap_or(false)
+        }).unwrap_or(rules.len());
+        rules.splice(match_at..match_at, append);
+    }
+
+    let (prepend, append) = (list(PREPEND_PROXIES), list(APPEND_PROXIES));
+    if !prepend.is_empty() || !append.is_empty() {
+        let added: Vec<Value> = prepend.iter().chain(&append)
+            .filter_map(|p| p.get("name").cloned())
+            .collect();
+        let proxies = sequence_mut(doc, "proxies");
+        proxies.retain(|p| !p.get("name").is_some_and(|n| added.contains(n)));
+        proxies.splice(0..0, prepend);
+        proxies.extend(append);
+    }
+
+    if let Some(groups) = overrides.get(GROUP_MEMBERS).and_then(|v| v.as_mapping()) {
+        for (name, ops) in groups {
+            let Some(group) = doc.get_mut("proxy-groups")
+                .and_then(|g| g.as_sequence_mut())
+                .and_then(|g| g.iter_mut().find(|g| g.get("name") == Some(name)))
+            else {
+                println!("{} {}", "警告: 覆盖文件引用了不存在的代理组, 已跳过:".yellow(), name.as_str().unwrap_or_default());
+                continue;
+            };
+            let group = group.as_mapping_mut().ok_or_else(|| anyhow!("代理组格式错误"))?;
+            let members = sequence_mut(group, "proxies");
+            let op = |key: &str| ops.get(key).and_then(|v| v.as_sequence()).cloned().unwrap_or_default();
+            let prepend = op("prepend");
+            let append = op("append");
+            members.retain(|m| !prepend.contains(m) && !append.contains(m));
+            members.splice(0..0, prepend);
+            members.extend(append);
+        }
+    }
+    Ok(())
+}
+
+/// 深度合并: 双方都是映射时递归合并, 否则直接替换
+fn merge_value(target: &mut Mapping, key: Value, value: Value) {
+    match (target.get_mut(&key), value) {
+        (Some(Value::Mapping(existing)), Value::Mapping(incoming)) => {
+            for (k, v) in incoming {
+                merge_value(existing, k, v);
+            }
+        }
+        (_, value) => {
+            target.insert(key, value);
+        }
+    }
+}
+
+/// 获取 (必要时创建) 指定键的列表
+fn sequence_mut<'a>(map: &'a mut Mapping, key: &str) -> &'a mut Vec<Value> {
+    let entry = map.entry(key.into()).or_insert_with(|| Value::Sequence(Vec::new()));
+    if !entry.is_sequence() {
+        *entry = Value::Sequence(Vec::new());
+    }
+    entry.as_sequence_mut().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    const BASE: &str = r#"
+mixed-port: 7890
+dns: {enable: false, ipv6: false}
+proxies:
+  - {name: a, type: ss}
+  - {name: b, type: ss}
+proxy-groups:
+  - {name: Proxy, type: select, proxies: [a, b]}
+rules:
+  - DOMAIN,x.com,Proxy
+  - MATCH,Proxy
+"#;
+
+    #[test]
+    fn applies_overrides() {
+        let cases = [
+            // 普通键深度合并
+            (
+                "mixed-port: 7891\ndns: {enable: true}",
+                "mixed-port: 7891\ndns: {enable: true, ipv6: false}",
+            ),
+            // prepend 规则在最前, append 规则在 MATCH 之前
+            (
+                "prepend-rules: ['DOMAIN,a.com,DIRECT']\nappend-rules: ['DOMAIN,z.com,DIRECT']",
+                "rules: ['DOMAIN,a.com,DIRECT', 'DOMAIN,x.com,Proxy', 'DOMAIN,z.com,DIRECT', 'MATCH,Proxy']",
+            ),
+            // 同名节点被替换
+            (
+                "prepend-proxies: [{name: b, type: vmess}]\nappend-proxies: [{name: c, type: trojan}]",
+                "proxies: [{name: b, type: vmess}, {name: a, type: ss}, {name: c, type: trojan}]",
+            ),
+            // 代理组成员去重后插入
+            (
+                "proxy-group-members: {Proxy: {prepend: [b, c], append: [DIRECT]}}",
+                "proxy-groups: [{name: Proxy, type: select, proxies: [b, c, a, DIRECT]}]",
+            ),
+        ];
+        for (overrides, expected) in cases {
+            let overrides = parse_override(overrides).unwrap().unwrap();
+            let mut config = yaml(BASE);
+            apply_override(&mut config, &overrides).unwrap();
+            for (key, value) in yaml(expected).as_mapping().unwrap() {
+                assert_eq!(config.get(key), Some(value), "{:?}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn skips_missing_groups() {
+        let overrides = parse_override("proxy-group-members: {Gone: {append: [a]}, Proxy: {append: [DIRECT]}}")
+            .unwrap()
+            .unwrap();
+        let mut config = yaml(BASE);
+        apply_override(&mut config, &overrides).unwrap();
+        assert_eq!(config["proxy-groups"][0]["proxies"], yaml("[a, b, DIRECT]"));
+    }
+
+    #[test]
+    fn rejects_malformed_overrides() {
+        let cases = [
+            "[1, 2]",
+            "prepend-rules: DOMAIN,a.com,DIRECT",
+            "proxy-group-members: [Proxy]",
+            "proxy-group-members: {Proxy: {insert: [a]}}",
+            "proxy-group-members: {Proxy: {append: a}}",
+        ];
+        for content in cases {
+            assert!(parse_override(content).is_err(), "{}", content);
+        }
+        assert!(parse_override("# 只有注释\n").unwrap().is_none());
+    }
+}
@@ -1 +1,9 @@
 pub mod api;
+pub mod controller;
+pub mod transport;
+pub mod error;
+pub mod convert;
+pub mod profile;
+pub mod merge;
+pub mod diff;
+pub mod release;
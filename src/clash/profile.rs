@@ -0,0 +1,171 @@
+use anyhow::{Result, Context, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use sha2::{Digest, Sha256};
+use crate::clash::controller::ACTIVE_CONFIG_FILE;
+use crate::utils::download::{DownloadInfo, SubscriptionUserInfo};
+
+/// 配置元信息文件, 与配置文件放在同一目录
+///
+/// 使用 JSON 格式, 避免被当作 Clash 配置列出
+pub const PROFILES_FILE: &str = "/etc/clash/profiles.json";
+
+/// 单个配置文件的来源与订阅信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// 订阅 URL 或本地文件路径
+    pub source: String,
+    pub added_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// 最近一次下载时的流量与到期信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_info: Option<SubscriptionUserInfo>,
+}
+
+impl Profile {
+    /// 是否来自远程订阅
+    pub fn is_remote(&self) -> bool {
+        self.source.starts_with("http://") || self.source.starts_with("https://")
+    }
+
+    /// 订阅到期时间
+    pub fn expire_at(&self) -> Option<DateTime<Utc>> {
+        self.user_info.as_ref()
+            .and_then(|info| info.expire)
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+    }
+
+    /// 距离到期的天数, 不足一天按一天计 (已过期为 0 或负数)
+    pub fn days_left(&self) -> Option<i64> {
+        self.expire_at().map(|at| {
+            let secs = (at - Utc::now()).num_seconds();
+            if secs > 0 { (secs + 86399) / 86400 } else { secs / 86400 }
+        })
+    }
+}
+
+/// 最近一次写入 config.yaml 的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveProfile {
+    /// 生成 config.yaml 的配置文件名, 从备份恢复或配置已删除等无法对应时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub applied_at: DateTime<Utc>,
+    /// 写入的 config.yaml 内容的 SHA-256, 用于发现手动修改
+    pub sha256: String,
+}
+
+/// 当前 config.yaml 的状态
+pub enum ActiveState {
+    /// 与最近一次写入的内容一致
+    Clean(ActiveProfile),
+    /// 写入后被手动修改过
+    Drifted(ActiveProfile),
+    /// 没有写入记录 (旧版本安装或手动创建)
+    Unknown,
+}
+
+/// 配置元信息登记表, 以配置文件名为键
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProfileStore {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<ActiveProfile>,
+}
+
+/// 计算内容的 SHA-256 (十六进制)
+pub fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+impl ProfileStore {
+    /// 读取登记表, 文件不存在时返回空表
+    pub fn load() -> Result<Self> {
+        let path = Path::new(PROFILES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).context(format!("读取 {} 失败", PROFILES_FILE))?;
+        serde_json::from_str(&content).context(format!("{} 格式错误", PROFILES_FILE))
+    }
+
+    /// 写回登记表
+    ///
+    /// 配置目录属于 root, 与其他配置文件一样先写入临时文件再通过 sudo 复制
+    pub fn save(&self) -> Result<()> {
+        let temp = tempfile::NamedTempFile::new()?;
+        fs::write(temp.path(), serde_json::to_string_pretty(self)?)?;
+        let status = Command::new("sudo")
+            .arg("cp")
+            .arg(temp.path())
+            .arg(PROFILES_FILE)
+            .status()?;
+        if !status.success() {
+            return Err(anyhow!("写入 {} 失败", PROFILES_FILE));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    /// 删除配置的记录
+    pub fn remove(&mut self, name: &str) -> Option<Profile> {
+        self.profiles.remove(name)
+    }
+
+    /// 配置重命名后迁移记录
+    pub fn rename(&mut self, name: &str, new_name: &str) {
+        if let Some(profile) = self.profiles.remove(name) {
+            self.profiles.insert(new_name.to_string(), profile);
+        }
+        if let Some(active) = self.active.as_mut().filter(|a| a.name.as_deref() == Some(name)) {
+            active.name = Some(new_name.to_string());
+        }
+    }
+
+    /// 记录写入 config.yaml 的内容及其来源配置
+    pub fn set_active(&mut self, name: Option<&str>, content: &str) {
+        self.active = Some(ActiveProfile {
+            name: name.map(|n| n.to_string()),
+            applied_at: Utc::now(),
+            sha256: sha256_hex(content.as_bytes()),
+        });
+    }
+
+    /// config.yaml 被有意修改 (如切换 Tun) 后更新记录, 保留来源配置
+    pub fn touch_active(&mut self, content: &str) {
+        let name = self.active.as_ref().and_then(|a| a.name.clone());
+        self.set_active(name.as_deref(), content);
+    }
+
+    /// 比较当前 config.yaml 与最近一次写入的内容
+    pub fn active_state(&self) -> ActiveState {
+        let Some(active) = self.active.clone() else { return ActiveState::Unknown };
+        match fs::read(ACTIVE_CONFIG_FILE) {
+            Ok(content) if sha256_hex(&content) == active.sha256 => ActiveState::Clean(active),
+            _ => ActiveState::Drifted(active),
+        }
+    }
+
+    /// 记录一次下载 (新增或更新), 保留原有的添加时间
+    pub fn record(&mut self, name: &str, source: &str, info: DownloadInfo) {
+        let now = Utc::now();
+        let added_at = self.profiles.get(name).map(|p| p.added_at).unwrap_or(now);
+        self.profiles.insert(name.to_string(), Profile {
+            source: source.to_string(),
+            added_at,
+            updated_at: now,
+            etag: info.etag,
+            user_info: info.user_info,
+        });
+    }
+}
@@ -0,0 +1,404 @@
+use anyhow::{Result, Context, anyhow};
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Mihomo 发布列表接口 (GitHub Releases API), 可通过 --release-api 或 CLASH_RELEASE_API 指向镜像
+pub const DEFAULT_RELEASE_API: &str = "https://api.github.com/repos/MetaCubeX/mihomo";
+
+/// 发布中整体校验文件的常见名称 (每行 "<sha256>  <文件名>")
+const CHECKSUM_FILES: &[&str] = &["checksums.txt", "sha256sums.txt", "SHA256SUMS", "checksums.sha256"];
+
+/// 缓存的发布列表在此时间内直接使用, 不再请求接口
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// 发布列表的本地缓存
+#[derive(Serialize, Deserialize)]
+struct ReleaseCache {
+    api: String,
+    fetched_at: DateTime<Utc>,
+    releases: Vec<Release>,
+}
+
+/// 解析出的可安装版本
+#[derive(Debug, Clone)]
+pub struct ResolvedRelease {
+    pub tag: String,
+    pub asset: Asset,
+    /// 安装包对应的架构名
+    pub arch: String,
+    /// `clash -v` 输出中应包含的版本号, 正式版为标签, 预发布版为 alpha-<commit>
+    pub version: String,
+    /// 安装包对应的 SHA-256 校验文件 (<安装包>.sha256 或整体的 checksums.txt)
+    pub checksum: Option<Asset>,
+}
+
+/// 版本选择方式
+#[derive(Debug, PartialEq)]
+enum VersionSpec {
+    /// 最新正式版
+    Latest,
+    /// 最新预发布版, 可按标签关键字 (alpha/beta) 筛选
+    Prerelease(Option<String>),
+    /// 版本前缀, 如 1 或 1.18, 选择匹配的最高正式版
+    Range(Vec<u64>),
+    /// 完整版本号或任意标签
+    Exact(String),
+}
+
+impl VersionSpec {
+    fn parse(spec: &str) -> Self {
+        let spec = spec.trim();
+        match spec.to_lowercase().as_str() {
+            "" | "latest" | "stable" => return VersionSpec::Latest,
+            "prerelease" | "pre" => return VersionSpec::Prerelease(None),
+            kw @ ("alpha" | "beta") => return VersionSpec::Prerelease(Some(kw.to_string())),
+            _ => {}
+        }
+        match parse_semver(spec) {
+            Some(parts) if parts.len() < 3 => VersionSpec::Range(parts),
+            Some(parts) => VersionSpec::Exact(format!("v{}", parts.iter().map(|p| p.to_string()).collect::<Vec<_>>().join("."))),
+            None => VersionSpec::Exact(spec.to_string()),
+        }
+    }
+}
+
+/// 解析 v1.18.0 / 1.18 形式的版本号, 非纯数字版本返回 None
+pub fn parse_semver(tag: &str) -> Option<Vec<u64>> {
+    let version = tag.strip_prefix('v').unwrap_or(tag);
+    let parts: Vec<u64> = version.split('.').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    (!parts.is_empty() && parts.len() <= 3).then_some(parts)
+}
+
+/// 从发布列表中选出符合要求的版本
+fn select_release<'a>(releases: &'a [Release], spec: &VersionSpec) -> Option<&'a Release> {
+    let stable = || releases.iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .filter_map(|r| parse_semver(&r.tag_name).map(|v| (v, r)));
+    match spec {
+        VersionSpec::Latest => stable().max_by(|a, b| a.0.cmp(&b.0)).map(|(_, r)| r),
+        VersionSpec::Range(prefix) => stable()
+            .filter(|(v, _)| v.starts_with(prefix))
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, r)| r),
+        VersionSpec::Prerelease(keyword) => releases.iter()
+            .filter(|r| !r.draft && r.prerelease)
+            .filter(|r| keyword.as_ref().map(|k| r.tag_name.to_lowercase().contains(k)).unwrap_or(true))
+            .max_by_key(|r| r.published_at),
+        VersionSpec::Exact(tag) => releases.iter().find(|r| !r.draft && r.tag_name == *tag),
+    }
+}
+
+/// 安装包格式, 按优先级排列
+const ASSET_FORMATS: &[&str] = &[".gz", ".tar.gz", ".zip", ".deb"];
+
+/// 选择与系统匹配的内核安装包, 同时返回文件名中的版本部分
+///
+/// 正式版命名为 mihomo-<os>-<arch>-<tag>.gz, 预发布版为 mihomo-<os>-<arch>-alpha-<commit>.gz;
+/// 没有 .gz 包时依次尝试 .tar.gz、.zip 和 .deb
+pub fn select_asset<'a>(release: &'a Release, os: &str, arch: &str) -> Option<(&'a Asset, &'a str)> {
+    let prefix = format!("mihomo-{}-{}-", os, arch);
+    ASSET_FORMATS.iter().find_map(|format| {
+        release.assets.iter().find_map(|asset| {
+            let stem = asset.name.strip_prefix(&prefix)?.strip_suffix(format)?;
+            // .gz 不匹配 .tar.gz
+            let matches = !stem.ends_with(".tar")
+                && (stem == release.tag_name || (release.prerelease && is_prerelease_stem(stem)));
+            matches.then_some((asset, stem))
+        })
+    })
+}
+
+/// 预发布包的版本部分, 如 alpha-1a2b3c4
+fn is_prerelease_stem(stem: &str) -> bool {
+    stem.split_once('-')
+        .map(|(channel, commit)| {
+            channel.chars().all(|c| c.is_ascii_lowercase())
+                && !commit.is_empty()
+                && commit.chars().all(|c| c.is_ascii_hexdigit())
+        })
+        .unwrap_or(false)
+}
+
+/// 根据版本要求和系统信息从发布列表中解析出下载地址
+///
+/// `arches` 按优先级排列, 使用发布中第一个存在安装包的架构
+pub fn resolve(releases: &[Release], spec: &str, os: &str, arches: &[String]) -> Result<ResolvedRelease> {
+    let parsed = VersionSpec::parse(spec);
+    let release = select_release(releases, &parsed)
+        .ok_or_else(|| anyhow!("发布列表中没有符合 {} 的版本", spec))?;
+    let (arch, (asset, stem)) = arches.iter()
+        .find_map(|arch| select_asset(release, os, arch).map(|found| (arch, found)))
+        .ok_or_else(|| {
+            anyhow!("版本 {} 没有适用于 {}/{} 的安装包", release.tag_name, os, arches.join("|"))
+        })?;
+    let per_asset = format!("{}.sha256", asset.name);
+    let checksum = release.assets.iter()
+        .find(|a| a.name == per_asset)
+        .or_else(|| release.assets.iter().find(|a| CHECKSUM_FILES.contains(&a.name.as_str())));
+    Ok(ResolvedRelease {
+        tag: release.tag_name.clone(),
+        asset: asset.clone(),
+        arch: arch.clone(),
+        version: stem.to_string(),
+        checksum: checksum.cloned(),
+    })
+}
+
+/// 从校验文件内容中查找指定文件的 SHA-256
+///
+/// 支持 sha256sum 格式 ("<hash>  <文件名>", 文件名前可带 *) 和只包含哈希值的单文件格式
+pub fn find_checksum(content: &str, filename: &str) -> Option<String> {
+    let is_hash = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+    let entries: Vec<&str> = content.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+    for line in &entries {
+        let mut parts = line.split_whitespace();
+        let (Some(hash), name) = (parts.next(), parts.next()) else { continue };
+        if !is_hash(hash) {
+            continue;
+        }
+        match name.map(|n| n.trim_start_matches('*')) {
+            Some(name) if Path::new(name).file_name().and_then(|n| n.to_str()) == Some(filename) => {
+                return Some(hash.to_lowercase());
+            }
+            None if entries.len() == 1 => return Some(hash.to_lowercase()),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 获取发布列表
+///
+/// 缓存未过期时直接使用缓存; 请求失败时退回到任意时间的缓存, 以便离线重装
+pub async fn fetch_releases(api: &str) -> Result<Vec<Release>> {
+    let api = api.trim_end_matches('/');
+    let cached = load_cache(api);
+    if let Some(cache) = &cached {
+        let age = (Utc::now() - cache.fetched_at).to_std().unwrap_or_default();
+        if age < CACHE_TTL {
+            return Ok(cache.releases.clone());
+        }
+    }
+
+    match request_releases(api).await {
+        Ok(releases) => {
+            save_cache(api, &releases);
+            Ok(releases)
+        }
+        Err(e) => match cached {
+            Some(cache) => {
+                println!("{} {}", "警告: 获取发布列表失败, 使用缓存:".yellow(), e);
+                println!("  缓存时间: {}", cache.fetched_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"));
+                Ok(cache.releases)
+            }
+            None => Err(e.context("获取发布列表失败，可使用 --version 指定完整版本号或通过 --file 从本地安装")),
+        },
+    }
+}
+
+async fn request_releases(api: &str) -> Result<Vec<Release>> {
+    let client = reqwest::Client::builder()
+        .user_agent("clash-cli/0.1.0")
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("Failed to build HTTP client")?;
+    let url = format!("{}/releases?per_page=100", api);
+    let releases = client.get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .send().await
+        .context(format!("请求 {} 失败", url))?
+        .error_for_status()
+        .context("发布接口返回错误")?
+        .json::<Vec<Release>>().await
+        .context("发布列表格式错误")?;
+    Ok(releases)
+}
+
+/// 缓存文件位置: $XDG_CACHE_HOME/clash-cli/releases.json 或 ~/.cache/clash-cli/releases.json
+fn cache_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("clash-cli").join("releases.json"))
+}
+
+/// 读取缓存, 接口地址不同时视为无缓存
+fn load_cache(api: &str) -> Option<ReleaseCache> {
+    let content = fs::read_to_string(cache_path()?).ok()?;
+    serde_json::from_str::<ReleaseCache>(&content).ok().filter(|c| c.api == api)
+}
+
+/// 写入缓存, 失败不影响安装
+fn save_cache(api: &str, releases: &[Release]) {
+    let Some(path) = cache_path() else { return };
+    let cache = ReleaseCache { api: api.to_string(), fetched_at: Utc::now(), releases: releases.to_vec() };
+    let result = path.parent()
+        .map(fs::create_dir_all)
+        .transpose()
+        .and_then(|_| fs::write(&path, serde_json::to_vec(&cache).unwrap_or_default()));
+    if let Err(e) = result {
+        println!("{} {}", "警告: 写入发布列表缓存失败:".yellow(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool, day: u32, assets: &[&str]) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            prerelease,
+            draft: false,
+            published_at: Some(format!("2026-01-{:02}T00:00:00Z", day).parse().unwrap()),
+            assets: assets.iter()
+                .map(|name| Asset { name: name.to_string(), browser_download_url: format!("https://dl/{}", name), size: 0 })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parses_version_specs() {
+        let cases = [
+            ("", VersionSpec::Latest),
+            ("latest", VersionSpec::Latest),
+            ("Stable", VersionSpec::Latest),
+            ("pre", VersionSpec::Prerelease(None)),
+            ("Alpha", VersionSpec::Prerelease(Some("alpha".to_string()))),
+            ("1", VersionSpec::Range(vec![1])),
+            ("v1.18", VersionSpec::Range(vec![1, 18])),
+            ("1.18.5", VersionSpec::Exact("v1.18.5".to_string())),
+            ("v1.18.05", VersionSpec::Exact("v1.18.5".to_string())),
+            ("Prerelease-alpha", VersionSpec::Exact("Prerelease-alpha".to_string())),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(VersionSpec::parse(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn selects_releases() {
+        let mut draft = release("v2.0.0", false, 9, &[]);
+        draft.draft = true;
+        let releases = vec![
+            release("v1.18.10", false, 3, &[]),
+            release("v1.9.0", false, 1, &[]),
+            release("v1.19.0", false, 5, &[]),
+            release("Prerelease-Alpha", true, 8, &[]),
+            release("v1.19.1-beta", true, 6, &[]),
+            draft,
+        ];
+        let cases = [
+            ("latest", Some("v1.19.0")),
+            ("1.18", Some("v1.18.10")),
+            ("1", Some("v1.19.0")),
+            ("1.9", Some("v1.9.0")),
+            ("1.20", None),
+            ("pre", Some("Prerelease-Alpha")),
+            ("alpha", Some("Prerelease-Alpha")),
+            ("beta", Some("v1.19.1-beta")),
+            ("1.18.10", Some("v1.18.10")),
+            ("2.0.0", None),
+        ];
+        for (spec, expected) in cases {
+            let selected = select_release(&releases, &VersionSpec::parse(spec)).map(|r| r.tag_name.as_str());
+            assert_eq!(selected, expected, "{}", spec);
+        }
+    }
+
+    #[test]
+    fn selects_assets() {
+        let stable = release("v1.19.0", false, 1, &[
+            "mihomo-linux-amd64-v1.19.0.tar.gz",
+            "mihomo-linux-amd64-v1.19.0.gz",
+            "mihomo-linux-amd64-compatible-v1.19.0.gz",
+            "mihomo-linux-arm64-v1.19.0.deb",
+            "mihomo-linux-armv7-v1.18.0.gz",
+        ]);
+        let alpha = release("Prerelease-Alpha", true, 1, &[
+            "mihomo-linux-amd64-alpha-1a2b3c4.gz",
+            "mihomo-linux-arm64-alpha-xyz.gz",
+        ]);
+        let cases = [
+            (&stable, "amd64", Some(("mihomo-linux-amd64-v1.19.0.gz", "v1.19.0"))),
+            (&stable, "amd64-compatible", Some(("mihomo-linux-amd64-compatible-v1.19.0.gz", "v1.19.0"))),
+            (&stable, "arm64", Some(("mihomo-linux-arm64-v1.19.0.deb", "v1.19.0"))),
+            (&stable, "armv7", None),
+            (&stable, "amd64-v3", None),
+            (&alpha, "amd64", Some(("mihomo-linux-amd64-alpha-1a2b3c4.gz", "alpha-1a2b3c4"))),
+            (&alpha, "arm64", None),
+        ];
+        for (release, arch, expected) in cases {
+            let selected = select_asset(release, "linux", arch).map(|(asset, stem)| (asset.name.as_str(), stem));
+            assert_eq!(selected, expected, "{} {}", release.tag_name, arch);
+        }
+    }
+
+    #[test]
+    fn resolves_first_available_arch() {
+        let releases = vec![release("v1.17.0", false, 1, &[
+            "mihomo-linux-amd64-v1.17.0.gz",
+            "mihomo-linux-amd64-compatible-v1.17.0.gz",
+            "mihomo-linux-amd64-compatible-v1.17.0.gz.sha256",
+        ])];
+        let arches = ["amd64-v1".to_string(), "amd64-compatible".to_string()];
+        let resolved = resolve(&releases, "1.17.0", "linux", &arches).unwrap();
+        assert_eq!(resolved.arch, "amd64-compatible");
+        assert_eq!(resolved.version, "v1.17.0");
+        assert_eq!(resolved.checksum.map(|a| a.name).as_deref(), Some("mihomo-linux-amd64-compatible-v1.17.0.gz.sha256"));
+        assert!(resolve(&releases, "1.17.0", "linux", &["arm64".to_string()]).is_err());
+    }
+
+    #[test]
+    fn finds_checksums() {
+        let hash = "A".repeat(64);
+        let other = "b".repeat(64);
+        let name = "mihomo-linux-amd64-v1.19.0.gz";
+        let cases = [
+            // sha256sum 格式, 文本模式与二进制模式
+            (format!("{}  {}\n{}  other.gz", hash, name, other), Some("a".repeat(64))),
+            (format!("{} *{}", hash, name), Some("a".repeat(64))),
+            // 带目录的文件名
+            (format!("{}  dist/{}", hash, name), Some("a".repeat(64))),
+            // 单独的哈希值
+            (format!("# checksum\n{}\n", hash), Some("a".repeat(64))),
+            // 多行时不接受没有文件名的哈希值
+            (format!("{}\n{}  other.gz", hash, other), None),
+            (format!("{}  other.gz", other), None),
+            ("not-a-hash  mihomo-linux-amd64-v1.19.0.gz".to_string(), None),
+            (String::new(), None),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(find_checksum(&content, name), expected, "{}", content);
+        }
+    }
+}
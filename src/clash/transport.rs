@@ -0,0 +1,169 @@
+use hyper::body::{Bytes, HttpBody};
+use hyperlocal::UnixConnector;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::path::PathBuf;
+use super::controller::{Controller, Endpoint};
+use super::error::{ApiResult, ClashApiError};
+
+/// 控制器 API 的底层传输方式
+///
+/// TCP 使用 reqwest, Unix Socket 使用 hyper + hyperlocal
+pub enum Transport {
+    Tcp {
+        client: reqwest::Client,
+        base_url: String,
+    },
+    Unix {
+        client: hyper::Client<UnixConnector>,
+        socket: PathBuf,
+        auth: Option<HeaderValue>,
+    },
+}
+
+impl Transport {
+    /// 根据控制器参数创建传输层
+    pub fn new(controller: &Controller) -> Self {
+        let auth = controller.secret.as_ref()
+            .and_then(|s| HeaderValue::from_str(&format!("Bearer {}", s)).ok());
+
+        match &controller.endpoint {
+            Endpoint::Tcp(base_url) => {
+                let mut headers = HeaderMap::new();
+                if let Some(value) = auth {
+                    headers.insert(AUTHORIZATION, value);
+                }
+                let client = reqwest::Client::builder()
+                    .default_headers(headers)
+                    .build()
+                    .unwrap_or_default();
+                Transport::Tcp { client, base_url: base_url.clone() }
+            }
+            Endpoint::Unix(socket) => Transport::Unix {
+                client: hyper::Client::builder().build(UnixConnector),
+                socket: socket.clone(),
+                auth,
+            },
+        }
+    }
+
+    /// 控制器端点描述, 用于错误提示
+    pub fn endpoint(&self) -> String {
+        match self {
+            Transport::Tcp { base_url, .. } => base_url.clone(),
+            Transport::Unix { socket, .. } => format!("unix:{}", socket.display()),
+        }
+    }
+
+    /// 发送请求, `path` 包含查询参数 (如 `/logs?level=info`)
+    pub async fn request(&self, method: Method, path: &str, body: Option<&serde_json::Value>) -> ApiResult<ApiResponse> {
+        match self {
+            Transport::Tcp { client, base_url } => {
+                let url = format!("{}{}", base_url, path);
+                let mut builder = client.request(method, &url);
+                if let Some(body) = body {
+                    builder = builder.json(body);
+                }
+                match builder.send().await {
+                    Ok(resp) => Ok(ApiResponse::Tcp(resp)),
+                    Err(e) if e.is_connect() => Err(self.connection_refused(&e)),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            Transport::Unix { client, socket, auth } => {
+                let uri: hyper::Uri = hyperlocal::Uri::new(socket, path).into();
+                let mut builder = hyper::Request::builder().method(method).uri(uri);
+                if let Some(value) = auth {
+                    builder = builder.header(AUTHORIZATION, value.clone());
+                }
+                let request = match body {
+                    Some(body) => builder
+                        .header(CONTENT_TYPE, "application/json")
+                        .body(hyper::Body::from(serde_json::to_vec(body)?)),
+                    None => builder.body(hyper::Body::empty()),
+                }.map_err(|e| ClashApiError::Transport(e.to_string()))?;
+                match client.request(request).await {
+                    Ok(resp) => Ok(ApiResponse::Unix(resp)),
+                    Err(e) if e.is_connect() => Err(self.connection_refused(&e)),
+                    Err(e) => Err(e.into()),
+                }
+            }
+        }
+    }
+
+    /// 构造连接失败错误, 原因取最底层的错误信息 (如 "Connection refused")
+    fn connection_refused(&self, err: &(dyn Error + 'static)) -> ClashApiError {
+        let mut cause = err;
+        while let Some(source) = cause.source() {
+            cause = source;
+        }
+        ClashApiError::ConnectionRefused {
+            endpoint: self.endpoint(),
+            reason: cause.to_string(),
+        }
+    }
+}
+
+/// 控制器 API 响应, 屏蔽两种传输方式的差异
+pub enum ApiResponse {
+    Tcp(reqwest::Response),
+    Unix(hyper::Response<hyper::Body>),
+}
+
+impl ApiResponse {
+    /// HTTP 状态码
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiResponse::Tcp(resp) => resp.status(),
+            ApiResponse::Unix(resp) => resp.status(),
+        }
+    }
+
+    /// 非成功状态码转换为对应的错误, `subject` 描述请求对象 (如节点名)
+    pub async fn error_for_status(self, subject: &str) -> ApiResult<Self> {
+        let status = self.status();
+        if status.is_success() {
+            return Ok(self);
+        }
+        match status {
+            StatusCode::UNAUTHORIZED => Err(ClashApiError::Unauthorized),
+            StatusCode::NOT_FOUND => Err(ClashApiError::NotFound(subject.to_string())),
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Err(ClashApiError::Timeout(subject.to_string())),
+            _ => {
+                // Clash 的错误响应一般为 {"message": "..."}
+                let body = self.bytes().await.unwrap_or_default();
+                let message = serde_json::from_slice::<serde_json::Value>(&body).ok()
+                    .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(|m| m.to_string()))
+                    .unwrap_or_else(|| String::from_utf8_lossy(&body).trim().to_string());
+                Err(ClashApiError::Status { status: status.as_u16(), message })
+            }
+        }
+    }
+
+    /// 读取下一个数据块 (用于 /traffic, /logs 等流式接口)
+    pub async fn chunk(&mut self) -> ApiResult<Option<Bytes>> {
+        match self {
+            ApiResponse::Tcp(resp) => Ok(resp.chunk().await?),
+            ApiResponse::Unix(resp) => match resp.body_mut().data().await {
+                Some(chunk) => Ok(Some(chunk?)),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// 读取完整响应体
+    pub async fn bytes(self) -> ApiResult<Bytes> {
+        match self {
+            ApiResponse::Tcp(resp) => Ok(resp.bytes().await?),
+            ApiResponse::Unix(resp) => Ok(hyper::body::to_bytes(resp.into_body()).await?),
+        }
+    }
+
+    /// 将响应体解析为 JSON
+    pub async fn json<T: DeserializeOwned>(self) -> ApiResult<T> {
+        let bytes = self.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
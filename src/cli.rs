@@ -1,10 +1,24 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::fmt;
+use std::path::PathBuf;
+use crate::clash::api::{DelayTest, DEFAULT_TEST_TIMEOUT, DEFAULT_TEST_URL};
 
 #[derive(Parser)]
 #[command(name = "clash-cli")]
 #[command(about = "Clash 管理命令行工具", long_about = None)]
 pub struct Cli {
+    /// Clash 外部控制器地址 (默认读取 /etc/clash/config.yaml 中的 external-controller)
+    #[arg(long, global = true, env = "CLASH_API_URL")]
+    pub api_url: Option<String>,
+
+    /// 通过 Unix Socket 访问控制器 (默认读取 external-controller-unix)
+    #[arg(long, global = true, env = "CLASH_API_SOCKET", conflicts_with = "api_url")]
+    pub api_socket: Option<PathBuf>,
+
+    /// Clash 外部控制器密钥 (默认读取 /etc/clash/config.yaml 中的 secret)
+    #[arg(long, global = true, env = "CLASH_SECRET", hide_env_values = true)]
+    pub secret: Option<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -13,13 +27,54 @@ pub struct Cli {
 pub enum Commands {
     /// 安装 Clash 并注册为系统服务
     Install {
-        /// 安装版本 (默认: latest)
+        /// 安装版本: latest、prerelease (或 alpha)、版本前缀 (如 1.18) 或完整版本号 (默认: latest)
         #[arg(short, long)]
         version: Option<String>,
+
+        /// 发布列表接口地址, 可指向镜像 (GitHub Releases API 格式)
+        #[arg(long, env = "CLASH_RELEASE_API", value_name = "URL", default_value = crate::clash::release::DEFAULT_RELEASE_API)]
+        release_api: String,
         
-        /// 从本地文件安装 (Mihomo .gz 包)
+        /// 从本地文件安装 (.gz、.tar.gz、.zip、.deb、解压后的目录或可执行文件)
         #[arg(short, long)]
         file: Option<String>,
+
+        /// 安装包的 SHA-256, 用于校验本地文件 (下载时默认使用发布中的校验文件)
+        #[arg(long, value_name = "HEX")]
+        sha256: Option<String>,
+
+        /// 指定安装包架构 (如 amd64-v3、armv7、mipsle-softfloat), 默认自动检测
+        #[arg(long)]
+        arch: Option<String>,
+
+        /// 只显示检测到的架构和将要下载的安装包, 不安装内核
+        #[arg(long)]
+        dry_run: bool,
+
+        /// 同时启用订阅定时更新, 参数为更新间隔 (如 6h、1d)
+        #[arg(long, value_name = "INTERVAL")]
+        auto_update: Option<String>,
+    },
+    /// 升级 Clash 内核 (仅替换二进制文件, 失败时自动恢复)
+    Upgrade {
+        /// 目标版本: latest、prerelease (或 alpha)、版本前缀 (如 1.18) 或完整版本号 (默认: latest)
+        version: Option<String>,
+
+        /// 发布列表接口地址, 可指向镜像 (GitHub Releases API 格式)
+        #[arg(long, env = "CLASH_RELEASE_API", value_name = "URL", default_value = crate::clash::release::DEFAULT_RELEASE_API)]
+        release_api: String,
+
+        /// 指定安装包架构 (如 amd64-v3、armv7、mipsle-softfloat), 默认自动检测
+        #[arg(long)]
+        arch: Option<String>,
+
+        /// 恢复升级前的内核 (再次执行可撤销)
+        #[arg(long, conflicts_with_all = ["version", "check"])]
+        rollback: bool,
+
+        /// 仅检查是否有新版本
+        #[arg(long)]
+        check: bool,
     },
     /// 卸载 Clash 服务和二进制文件
     Uninstall,
@@ -32,9 +87,12 @@ pub enum Commands {
     
     /// 管理节点/代理
     Node {
-        /// 交互式选择代理
+        /// 交互式选择代理 (未指定子命令且在终端中运行时的默认行为)
         #[arg(short, long)]
         select: bool,
+
+        #[command(subcommand)]
+        action: Option<NodeAction>,
     },
     
     /// 设置路由策略 (Global, Rule, Direct)
@@ -58,6 +116,12 @@ pub enum Commands {
         action: ProxyAction,
     },
 
+    /// 管理活跃连接 (list/close/close-all)
+    Conn {
+        #[command(subcommand)]
+        action: ConnAction,
+    },
+
     /// 查看 Clash 运行状态
     Status,
 
@@ -78,7 +142,229 @@ pub enum ConfigAction {
     /// 列出可用配置
     List,
     /// 选择当前激活的配置
-    Select,
+    Select {
+        /// 配置名称 (可省略 .yaml 后缀), 省略时交互式选择
+        name: Option<String>,
+    },
+    /// 从记录的来源重新下载配置
+    Update {
+        /// 配置名称 (可省略 .yaml 后缀)
+        #[arg(required_unless_present = "all")]
+        name: Option<String>,
+        /// 更新所有已记录来源的配置
+        #[arg(long, conflicts_with = "name")]
+        all: bool,
+    },
+    /// 删除配置
+    Remove {
+        /// 配置名称 (可省略 .yaml 后缀)
+        name: String,
+        /// 跳过确认
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// 重命名配置
+    Rename {
+        /// 原配置名称
+        name: String,
+        /// 新配置名称
+        new_name: String,
+    },
+    /// 显示配置摘要 (节点、代理组、规则、端口、DNS 等)
+    Show {
+        /// 配置名称 (可省略 .yaml 后缀)
+        name: String,
+    },
+    /// 导出配置内容
+    Export {
+        /// 配置名称 (可省略 .yaml 后缀)
+        name: String,
+        /// 隐藏密码、UUID、密钥和订阅链接中的令牌
+        #[arg(long)]
+        redact: bool,
+        /// 输出到文件 (默认输出到标准输出)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// 比较两份配置的节点、代理组、规则、DNS、TUN 和端口设置
+    Diff {
+        /// 原配置名称或文件路径
+        a: String,
+        /// 新配置名称或文件路径 (默认为当前生效的 config.yaml)
+        b: Option<String>,
+    },
+    /// 恢复 config.yaml 的历史版本
+    Rollback {
+        /// 备份序号 (1 为最近一次) 或备份文件名, 省略时交互式选择
+        backup: Option<String>,
+        /// 仅列出可用的备份
+        #[arg(short, long, conflicts_with = "backup")]
+        list: bool,
+    },
+    /// 管理本地覆盖配置 (切换配置时合并到订阅之上)
+    Override {
+        #[command(subcommand)]
+        action: OverrideAction,
+    },
+    /// 管理订阅定时更新 (systemd timer)
+    AutoUpdate {
+        #[command(subcommand)]
+        action: AutoUpdateAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OverrideAction {
+    /// 使用 $EDITOR 编辑覆盖文件
+    Edit,
+    /// 显示覆盖文件内容
+    Show,
+    /// 显示覆盖文件对配置的改动
+    Diff {
+        /// 配置名称 (默认为当前使用的配置)
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AutoUpdateAction {
+    /// 启用定时更新
+    On {
+        /// 更新间隔, 使用 systemd 时间格式 (如 30min、6h、1d)
+        #[arg(short, long, default_value = "6h")]
+        interval: String,
+    },
+    /// 停用定时更新
+    Off,
+    /// 查看定时更新状态
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum NodeAction {
+    /// 非交互式切换节点
+    Set {
+        /// 代理组名称 (不区分大小写)
+        group: String,
+        /// 节点名称或匹配模式
+        node: String,
+        /// 匹配方式
+        #[arg(short, long, value_enum, default_value_t = MatchMode::Auto)]
+        mode: MatchMode,
+    },
+    /// 并发测试代理组内所有节点的延迟
+    Test {
+        /// 代理组名称 (默认测试所有代理组中的节点)
+        group: Option<String>,
+        #[command(flatten)]
+        test: DelayTestArgs,
+        /// 使用 Mihomo 的代理组测速接口 (/group/{name}/delay)，需要指定代理组
+        #[arg(long, requires = "group")]
+        group_api: bool,
+    },
+    /// 测速并自动切换到延迟最低的节点
+    Auto {
+        /// 代理组名称 (需为 Selector 类型)
+        group: String,
+        /// 只在名称匹配该正则的节点中选择 (不区分大小写, 如 "香港|HK")
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// 容差 (毫秒): 当前节点可用且不比最快节点慢超过该值时不切换
+        #[arg(long, default_value_t = 50)]
+        tolerance: u64,
+        /// 持续运行, 定期重新评估
+        #[arg(short, long)]
+        watch: bool,
+        /// 切换幅度 (毫秒, 仅用于 --watch 的后续评估): 当前节点比最快节点慢超过该值才切换,
+        /// 默认与 --tolerance 相同
+        #[arg(long, requires = "watch")]
+        margin: Option<u64>,
+        /// 重新评估间隔 (秒, 仅用于 --watch)
+        #[arg(short, long, default_value_t = 300)]
+        interval: u64,
+        #[command(flatten)]
+        test: DelayTestArgs,
+    },
+    /// 列出代理组成员、当前选择和最近延迟
+    List {
+        /// 代理组名称 (默认列出所有代理组)
+        group: Option<String>,
+        /// 以 JSON 格式输出
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// 延迟测试参数
+#[derive(Args, Debug, Clone)]
+pub struct DelayTestArgs {
+    /// 最大并发测试数
+    #[arg(short, long, default_value_t = 16)]
+    pub concurrency: usize,
+    /// 单个节点的超时时间 (毫秒)
+    #[arg(short, long, default_value_t = DEFAULT_TEST_TIMEOUT)]
+    pub timeout: u64,
+    /// 测试地址
+    #[arg(short, long, default_value = DEFAULT_TEST_URL)]
+    pub url: String,
+}
+
+impl DelayTestArgs {
+    /// 转换为 API 的延迟测试参数
+    pub fn delay_test(&self) -> DelayTest {
+        DelayTest { url: self.url.clone(), timeout_ms: self.timeout }
+    }
+}
+
+/// 节点名称匹配方式
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum MatchMode {
+    /// 依次尝试精确、忽略大小写、子串匹配
+    Auto,
+    /// 精确匹配
+    Exact,
+    /// 忽略大小写的精确匹配
+    IgnoreCase,
+    /// 忽略大小写的子串匹配
+    Substring,
+    /// 正则表达式匹配
+    Regex,
+}
+
+#[derive(Subcommand)]
+pub enum ConnAction {
+    /// 列出活跃连接
+    List {
+        #[command(flatten)]
+        filter: ConnFilter,
+    },
+    /// 关闭指定 ID 的连接
+    Close {
+        /// 连接 ID (可使用 conn list 中显示的前缀)
+        id: String,
+    },
+    /// 关闭所有连接 (指定过滤条件时只关闭匹配的连接)
+    CloseAll {
+        #[command(flatten)]
+        filter: ConnFilter,
+    },
+}
+
+/// 连接过滤条件 (均为不区分大小写的子串匹配)
+#[derive(Args, Debug, Default, Clone)]
+pub struct ConnFilter {
+    /// 按目标主机或 IP 过滤
+    #[arg(long)]
+    pub host: Option<String>,
+    /// 按命中的规则过滤
+    #[arg(long)]
+    pub rule: Option<String>,
+    /// 按代理链中的节点或策略组过滤
+    #[arg(long)]
+    pub chain: Option<String>,
+    /// 按进程名或进程路径过滤
+    #[arg(long)]
+    pub process: Option<String>,
 }
 
 #[derive(Subcommand)]
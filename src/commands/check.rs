@@ -1,5 +1,6 @@
-use anyhow::{Result, Context};
-use crate::clash::api::ClashClient;
+use anyhow::Result;
+use crate::clash::api::{ClashClient, DelayTest};
+use crate::clash::error::ClashApiError;
 use colored::*;
 
 /// 状态检测命令入口
@@ -7,7 +8,7 @@ use colored::*;
 /// 检测所有 Selector 类型的代理组当前选中节点的延迟
 pub async fn run() -> Result<()> {
     let client = ClashClient::new();
-    let proxies = client.get_proxies().await.context("无法连接到 Clash API。Clash 是否正在运行？")?;
+    let proxies = client.get_proxies().await?;
     
     let mut groups: Vec<&String> = proxies.iter()
         .filter(|(_, p)| p.proxy_type == "Selector")
@@ -28,7 +29,7 @@ pub async fn run() -> Result<()> {
                  print!("代理组 {}: 当前选中节点 [{}] ... ", group_name.cyan(), now.yellow());
                  
                  // 测试节点延迟
-                 match client.delay_test(now).await {
+                 match client.delay_test(now, &DelayTest::default()).await {
                      Ok(delay) => {
                          let status = if delay < 200 {
                              format!("{} ms", delay).green()
@@ -39,8 +40,16 @@ pub async fn run() -> Result<()> {
                          };
                          println!("延迟: {}", status);
                      },
-                     Err(_) => {
-                         println!("{}", "超时/错误".red());
+                     Err(ClashApiError::Timeout(_)) => {
+                         println!("{}", "超时".red());
+                     },
+                     // 连接或鉴权失败时后续检测没有意义，直接中止
+                     Err(e @ (ClashApiError::ConnectionRefused { .. } | ClashApiError::Unauthorized)) => {
+                         println!("{}", e.short().red());
+                         return Err(e.into());
+                     },
+                     Err(e) => {
+                         println!("{} ({})", e.short().red(), e);
                      }
                  }
             }
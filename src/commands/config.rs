@@ -1,21 +1,59 @@
 use anyhow::{Result, Context, anyhow};
-use crate::cli::ConfigAction;
-use crate::utils::download;
+use crate::cli::{AutoUpdateAction, ConfigAction, OverrideAction};
+use crate::clash::api::ClashClient;
+use crate::commands::{install, proxy, tun};
+use crate::clash::{convert, diff, merge};
+use crate::clash::profile::{ActiveState, Profile, ProfileStore};
+use crate::utils::download::{self, DownloadInfo};
+use crate::utils::format::format_bytes;
 use std::path::{Path};
 use std::fs;
 use colored::*;
 use dialoguer::{Select, theme::ColorfulTheme};
+use std::path::PathBuf;
 use std::process::Command;
+use std::time::Duration;
 
 const CONFIG_DIR: &str = "/etc/clash";
 const ACTIVE_CONFIG: &str = "config.yaml";
 
+/// config.yaml 历史版本目录 (子目录中的文件不会被当作配置列出)
+const BACKUP_DIR: &str = "/etc/clash/backups";
+/// 最多保留的备份数量
+const MAX_BACKUPS: usize = 10;
+/// 备份文件名中的时间格式
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+/// 切换配置后等待 Clash 恢复正常的时间
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 剩余流量低于总量的该比例时发出警告
+const QUOTA_WARN_RATIO: f64 = 0.1;
+/// 距离到期不足该天数时发出警告
+const EXPIRE_WARN_DAYS: i64 = 7;
+
 /// 配置管理命令入口
 pub async fn run(action: ConfigAction) -> Result<()> {
     match action {
         ConfigAction::Add { url, name } => add_config(url, name).await?,
         ConfigAction::List => { list_configs()?; },
-        ConfigAction::Select => select_config()?,
+        ConfigAction::Select { name } => select_config(name).await?,
+        ConfigAction::Update { name, all } => update_configs(name, all).await?,
+        ConfigAction::Remove { name, yes } => remove_config(&name, yes)?,
+        ConfigAction::Rename { name, new_name } => rename_config(&name, &new_name)?,
+        ConfigAction::Show { name } => show_config(&name)?,
+        ConfigAction::Export { name, redact, output } => export_config(&name, redact, output)?,
+        ConfigAction::Diff { a, b } => diff_configs(&a, b.as_deref())?,
+        ConfigAction::Rollback { backup, list } => rollback(backup, list).await?,
+        ConfigAction::Override { action } => match action {
+            OverrideAction::Edit => edit_override().await?,
+            OverrideAction::Show => show_override()?,
+            OverrideAction::Diff { name } => diff_override(name)?,
+        },
+        ConfigAction::AutoUpdate { action } => match action {
+            AutoUpdateAction::On { interval } => install::enable_auto_update(&interval)?,
+            AutoUpdateAction::Off => install::disable_auto_update()?,
+            AutoUpdateAction::Status => install::auto_update_status()?,
+        },
     }
     Ok(())
 }
@@ -25,22 +63,14 @@ pub async fn run(action: ConfigAction) -> Result<()> {
 /// 支持从 URL 下载或从本地文件复制
 async fn add_config(url: String, name: Option<String>) -> Result<()> {
     let filename = if let Some(n) = name {
-        if n.ends_with(".yaml") || n.ends_with(".yml") {
-            n
-        } else {
-            format!("{}.yaml", n)
-        }
+        profile_filename(&n)?
     } else {
         // 从 URL 推断文件名
         let url_path = url.split('?').next().unwrap(); // remove query params
         let name = Path::new(url_path).file_name()
             .and_then(|s| s.to_str())
             .unwrap_or("subscription.yaml");
-         if name.ends_with(".yaml") || name.ends_with(".yml") {
-            name.to_string()
-        } else {
-            format!("{}.yaml", name)
-        }
+        profile_filename(name)?
     };
 
     println!("正在添加配置: {}", filename);
@@ -48,45 +78,443 @@ async fn add_config(url: String, name: Option<String>) -> Result<()> {
     let temp_dir = tempfile::tempdir()?;
     let temp_path = temp_dir.path().join(&filename);
     
-    if url.starts_with("http") {
-        download::download_file(&url, &temp_path).await?;
+    let source = if url.starts_with("http") {
+        url.clone()
     } else {
-        // 本地文件
-        fs::copy(&url, &temp_path).context("复制本地文件失败")?;
-    }
-    
-    // 验证配置文件格式
-    if let Err(e) = validate_config_file(&temp_path) {
-        return Err(anyhow!("配置文件校验失败: {}\n提示: 请确保订阅链接是 Clash 格式 (通常包含 &flag=clash)", e));
-    }
+        let path = fs::canonicalize(&url).unwrap_or_else(|_| Path::new(&url).to_path_buf());
+        path.display().to_string()
+    };
+    let info = fetch_profile(&source, &temp_path).await?;
     
     // 移动到配置目录 /etc/clash/
     let target_path = Path::new(CONFIG_DIR).join(&filename);
     println!("正在安装到 {}...", target_path.display());
+    install_file(&temp_path, &target_path)?;
     
+    println!("{}", "配置添加成功。".green());
+
+    // 记录来源与订阅信息, 失败不影响已添加的配置
+    let record = ProfileStore::load().and_then(|mut store| {
+        store.record(&filename, &source, info);
+        if let Some(profile) = store.get(&filename) {
+            print_profile_details(profile);
+        }
+        store.save()
+    });
+    if let Err(e) = record {
+        println!("{} {:#}", "警告: 保存配置元信息失败:".yellow(), e);
+    }
+
+    // 检查是否应该自动应用此配置
+    // 如果当前只有一个有效配置（即刚添加的这个），或者之前的 config.yaml 是默认生成的（简单检查）
+    let configs = list_configs_internal()?;
+    if configs.len() == 1 {
+        println!("检测到这是唯一的配置文件，正在自动应用...");
+        apply_config(&filename, &mut ProfileStore::load()?).await?;
+    }
+
+    Ok(())
+}
+
+/// 从 URL 下载或从本地文件复制到 `temp_path`, 并完成格式转换与校验
+async fn fetch_profile(source: &str, temp_path: &Path) -> Result<DownloadInfo> {
+    let info = if source.starts_with("http") {
+        download::download_file(source, temp_path).await?
+    } else {
+        // 本地文件
+        fs::copy(source, temp_path).context("复制本地文件失败")?;
+        DownloadInfo::default()
+    };
+
+    // 通用订阅 (Base64 或分享链接列表) 自动转换为 Clash 配置
+    convert_if_needed(temp_path)?;
+
+    // 验证配置文件格式
+    if let Err(e) = validate_config_file(temp_path) {
+        return Err(anyhow!("配置文件校验失败: {}\n提示: 请确保订阅链接是 Clash 格式 (通常包含 &flag=clash)", e));
+    }
+    test_with_core(temp_path)?;
+    Ok(info)
+}
+
+/// 通过 sudo 复制文件到配置目录
+fn install_file(source: &Path, target: &Path) -> Result<()> {
     let status = Command::new("sudo")
         .arg("cp")
-        .arg(temp_path)
-        .arg(&target_path)
+        .arg(source)
+        .arg(target)
         .status()?;
         
     if !status.success() {
         return Err(anyhow!("复制配置文件失败"));
     }
-    
-    println!("{}", "配置添加成功。".green());
+    Ok(())
+}
 
-    // 检查是否应该自动应用此配置
-    // 如果当前只有一个有效配置（即刚添加的这个），或者之前的 config.yaml 是默认生成的（简单检查）
-    let configs = list_configs_internal()?;
-    if configs.len() == 1 {
-        println!("检测到这是唯一的配置文件，正在自动应用...");
-        apply_config(&filename)?;
+/// 补全配置文件名的 .yaml 后缀
+fn config_filename(name: &str) -> String {
+    if name.ends_with(".yaml") || name.ends_with(".yml") {
+        name.to_string()
+    } else {
+        format!("{}.yaml", name)
+    }
+}
+
+/// 更新一个或全部配置
+async fn update_configs(name: Option<String>, all: bool) -> Result<()> {
+    let mut store = ProfileStore::load()?;
+    let names: Vec<String> = if all {
+        store.profiles.keys()
+            .filter(|n| Path::new(CONFIG_DIR).join(n).exists())
+            .cloned()
+            .collect()
+    } else {
+        let filename = profile_filename(name.as_deref().unwrap_or_default())?;
+        if store.get(&filename).is_none() {
+            return Err(anyhow!("配置 {} 没有记录来源，请使用 config add 重新添加", filename));
+        }
+        vec![filename]
+    };
+    if names.is_empty() {
+        println!("没有可更新的配置。");
+        return Ok(());
+    }
+
+    let mut failed = Vec::new();
+    for filename in &names {
+        println!("{} {}", "==>".cyan(), filename.bold());
+        if let Err(e) = update_one(&mut store, filename).await {
+            println!("  {} {:#}", "更新失败:".red(), e);
+            failed.push(filename.clone());
+        }
+    }
+    // 已更新的配置信息即使部分失败也需要保存
+    store.save()?;
+
+    if !failed.is_empty() {
+        return Err(anyhow!("{} 个配置更新失败: {}", failed.len(), failed.join(", ")));
+    }
+    Ok(())
+}
+
+/// 更新单个配置, 内容有变化时才替换文件, 如果是当前激活的配置则重新应用
+async fn update_one(store: &mut ProfileStore, filename: &str) -> Result<()> {
+    let source = store.get(filename)
+        .map(|p| p.source.clone())
+        .ok_or_else(|| anyhow!("没有记录来源"))?;
+    let target_path = Path::new(CONFIG_DIR).join(filename);
+    let old_content = fs::read_to_string(&target_path).unwrap_or_default();
+
+    let temp_dir = tempfile::tempdir()?;
+    let temp_path = temp_dir.path().join(filename);
+    let info = fetch_profile(&source, &temp_path).await?;
+    let new_content = fs::read_to_string(&temp_path)?;
+
+    let previous = store.get(filename).cloned();
+    store.record(filename, &source, info);
+    if let Some(profile) = store.get(filename) {
+        print_profile_details(profile);
     }
 
+    if new_content == old_content {
+        println!("  内容未变化。");
+        return Ok(());
+    }
+
+    let is_active = match store.active.as_ref() {
+        Some(active) => active.name.as_deref() == Some(filename),
+        // 没有切换记录时, 合并覆盖文件后与 config.yaml 内容一致即视为当前配置
+        None => current_profile().as_deref() == Some(filename),
+    };
+
+    print_proxy_changes(&old_content, &new_content);
+
+    // 当前配置先用新内容重新应用, 成功后才覆盖原文件, 失败时保留原配置及其记录
+    if is_active {
+        println!("  这是当前使用的配置，正在重新应用...");
+        if let Err(e) = apply_profile(filename, &new_content, store).await {
+            match previous {
+                Some(profile) => store.profiles.insert(filename.to_string(), profile),
+                None => store.remove(filename),
+            };
+            return Err(e.context("新配置应用失败，已保留原配置"));
+        }
+    }
+    install_file(&temp_path, &target_path)?;
+    println!("  {}", "配置已更新。".green());
     Ok(())
 }
 
+/// 打印新旧配置中节点的增减
+fn print_proxy_changes(old: &str, new: &str) {
+    let names = |content: &str| -> Vec<String> {
+        serde_yaml::from_str::<serde_yaml::Value>(content).ok()
+            .and_then(|doc| doc.get("proxies").and_then(|p| p.as_sequence()).cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|p| p.get("name").and_then(|n| n.as_str()).map(|n| n.to_string()))
+            .collect()
+    };
+    let old_names = names(old);
+    let new_names = names(new);
+    let added: Vec<&String> = new_names.iter().filter(|n| !old_names.contains(n)).collect();
+    let removed: Vec<&String> = old_names.iter().filter(|n| !new_names.contains(n)).collect();
+
+    println!("  节点: {} 个 (新增 {}, 移除 {})",
+        new_names.len(), added.len().to_string().green(), removed.len().to_string().red());
+    for name in added {
+        println!("    {} {}", "+".green(), name);
+    }
+    for name in removed {
+        println!("    {} {}", "-".red(), name);
+    }
+}
+
+/// 校验配置名称并补全后缀
+///
+/// 名称只能是配置目录中的文件名, 不能包含路径分隔符或 `..`;
+/// config.yaml 和覆盖文件不是可操作的配置, 与 config list 一样排除
+fn profile_filename(name: &str) -> Result<String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(anyhow!("无效的配置名称: {}", name));
+    }
+    let filename = config_filename(name);
+    if !is_profile_name(&filename) {
+        return Err(anyhow!("{} 和 {} 是保留的文件名，不是可用的配置", ACTIVE_CONFIG, merge::OVERRIDE_FILE));
+    }
+    Ok(filename)
+}
+
+/// 解析配置名称并确认文件存在, 返回文件名和路径
+fn existing_profile(name: &str) -> Result<(String, PathBuf)> {
+    let filename = profile_filename(name)?;
+    let path = Path::new(CONFIG_DIR).join(&filename);
+    if !path.is_file() {
+        return Err(anyhow!("配置 {} 不存在，使用 clash-cli config list 查看可用配置", filename));
+    }
+    Ok((filename, path))
+}
+
+/// 删除配置文件及其元信息
+fn remove_config(name: &str, yes: bool) -> Result<()> {
+    let (filename, path) = existing_profile(name)?;
+    if current_profile().as_deref() == Some(filename.as_str()) {
+        println!("{}", format!("{} 是当前使用的配置，删除后 config.yaml 保持不变。", filename).yellow());
+    }
+    if !yes {
+        let confirm = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("确定删除配置 {}?", filename))
+            .default(false)
+            .interact()?;
+        if !confirm {
+            println!("已取消。");
+            return Ok(());
+        }
+    }
+
+    let status = Command::new("sudo").arg("rm").arg("-f").arg(&path).status()?;
+    if !status.success() {
+        return Err(anyhow!("删除 {} 失败", path.display()));
+    }
+    let mut store = ProfileStore::load()?;
+    let removed = store.remove(&filename).is_some();
+    let active = store.active.as_mut().filter(|a| a.name.as_deref() == Some(filename.as_str()));
+    // config.yaml 保持不变, 但不再关联到任何配置
+    let was_active = active.map(|a| a.name = None).is_some();
+    if removed || was_active {
+        store.save()?;
+    }
+    println!("{}", format!("已删除配置 {}。", filename).green());
+    Ok(())
+}
+
+/// 重命名配置文件并迁移元信息
+fn rename_config(name: &str, new_name: &str) -> Result<()> {
+    let (filename, path) = existing_profile(name)?;
+    let new_filename = profile_filename(new_name)?;
+    let new_path = Path::new(CONFIG_DIR).join(&new_filename);
+    if new_path.exists() {
+        return Err(anyhow!("配置 {} 已存在", new_filename));
+    }
+
+    let status = Command::new("sudo").arg("mv").arg(&path).arg(&new_path).status()?;
+    if !status.success() {
+        return Err(anyhow!("重命名 {} 失败", filename));
+    }
+    let mut store = ProfileStore::load()?;
+    let is_active = store.active.as_ref().is_some_and(|a| a.name.as_deref() == Some(filename.as_str()));
+    if store.get(&filename).is_some() || is_active {
+        store.rename(&filename, &new_filename);
+        store.save()?;
+    }
+    println!("{}", format!("已将 {} 重命名为 {}。", filename, new_filename).green());
+    Ok(())
+}
+
+/// 显示配置摘要
+fn show_config(name: &str) -> Result<()> {
+    let (filename, path) = existing_profile(name)?;
+    let content = fs::read_to_string(&path).context(format!("读取 {} 失败", path.display()))?;
+    let doc: serde_yaml::Value = serde_yaml::from_str(&content).context("解析配置文件失败")?;
+
+    println!("{} ({})", filename.bold(), format_bytes(content.len() as u64));
+    if let Some(profile) = ProfileStore::load().ok().as_ref().and_then(|s| s.get(&filename)) {
+        print_profile_details(profile);
+    }
+
+    let seq = |key: &str| doc.get(key).and_then(|v| v.as_sequence()).cloned().unwrap_or_default();
+    let map_len = |key: &str| doc.get(key).and_then(|v| v.as_mapping()).map(|m| m.len()).unwrap_or(0);
+    // 按 type 字段统计数量, 如 "vmess 3, trojan 2"
+    let by_type = |items: &[serde_yaml::Value]| -> String {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for item in items {
+            let kind = item.get("type").and_then(|t| t.as_str()).unwrap_or("unknown").to_string();
+            match counts.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((kind, 1)),
+            }
+        }
+        counts.iter().map(|(k, n)| format!("{} {}", k, n)).collect::<Vec<_>>().join(", ")
+    };
+    let value = |key: &str| doc.get(key).map(|v| match v {
+        serde_yaml::Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+    });
+
+    let proxies = seq("proxies");
+    let groups = seq("proxy-groups");
+    println!();
+    println!("节点:       {} ({})", proxies.len().to_string().cyan(), by_type(&proxies));
+    println!("代理组:     {} ({})", groups.len().to_string().cyan(), by_type(&groups));
+    println!("规则:       {}", seq("rules").len().to_string().cyan());
+    println!("节点提供者: {}", map_len("proxy-providers").to_string().cyan());
+    println!("规则提供者: {}", map_len("rule-providers").to_string().cyan());
+
+    let ports: Vec<String> = ["mixed-port", "port", "socks-port", "redir-port", "tproxy-port"].iter()
+        .filter_map(|key| value(key).map(|v| format!("{} {}", key, v)))
+        .collect();
+    println!("端口:       {}", if ports.is_empty() { "未设置".to_string() } else { ports.join(", ") });
+    println!("模式:       {}", value("mode").unwrap_or_else(|| "rule".to_string()));
+    println!("局域网访问: {}", value("allow-lan").unwrap_or_else(|| "false".to_string()));
+    println!("控制器:     {}", value("external-controller").unwrap_or_else(|| "未设置".to_string()));
+
+    let dns = doc.get("dns");
+    let dns_enabled = dns.and_then(|d| d.get("enable")).and_then(|v| v.as_bool()).unwrap_or(false);
+    if dns_enabled {
+        let mode = dns.and_then(|d| d.get("enhanced-mode")).and_then(|v| v.as_str()).unwrap_or("normal");
+        println!("DNS:        已启用 ({})", mode);
+    } else {
+        println!("DNS:        未启用");
+    }
+    let tun_enabled = doc.get("tun").and_then(|t| t.get("enable")).and_then(|v| v.as_bool()).unwrap_or(false);
+    println!("Tun:        {}", if tun_enabled { "已启用" } else { "未启用" });
+    Ok(())
+}
+
+/// 导出时需要隐藏的字段 (不区分大小写)
+const SENSITIVE_KEYS: &[&str] = &[
+    "password", "uuid", "private-key", "private-key-passphrase", "pre-shared-key", "psk", "auth", "auth-str",
+    "authentication", "obfs-password", "token", "secret", "username", "short-id", "public-key",
+];
+
+/// 导出配置, 可选择隐藏敏感信息
+fn export_config(name: &str, redact: bool, output: Option<PathBuf>) -> Result<()> {
+    let (_, path) = existing_profile(name)?;
+    let content = fs::read_to_string(&path).context(format!("读取 {} 失败", path.display()))?;
+    let exported = if redact {
+        let mut doc: serde_yaml::Value = serde_yaml::from_str(&content).context("解析配置文件失败")?;
+        redact_value(&mut doc);
+        serde_yaml::to_string(&doc)?
+    } else {
+        content
+    };
+
+    match output {
+        Some(file) => {
+            fs::write(&file, exported).context(format!("写入 {} 失败", file.display()))?;
+            eprintln!("已导出到 {}", file.display());
+        }
+        None => print!("{}", exported),
+    }
+    Ok(())
+}
+
+/// 订阅与规则集链接所在的顶层字段, 令牌常位于这些链接的路径或查询参数中
+const PROVIDER_KEYS: &[&str] = &["proxy-providers", "rule-providers"];
+
+/// 递归隐藏敏感字段和链接中的用户信息, 订阅与规则集链接只保留协议、主机名和端口
+///
+/// 其他链接 (如健康检查地址) 只去掉用户信息, 保证导出的配置仍可使用
+fn redact_value(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            for (key, v) in map.iter_mut() {
+                let key = key.as_str().unwrap_or_default().to_lowercase();
+                if SENSITIVE_KEYS.contains(&key.as_str()) {
+                    redact_secret(v);
+                    continue;
+                }
+                if PROVIDER_KEYS.contains(&key.as_str()) {
+                    for provider in v.as_mapping_mut().into_iter().flat_map(|m| m.values_mut()) {
+                        if let Some(url) = provider.get_mut("url") {
+                            if let Some(redacted) = url.as_str().and_then(redact_url) {
+                                *url = redacted.into();
+                            }
+                        }
+                    }
+                }
+                redact_value(v);
+            }
+        }
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(redact_value),
+        serde_yaml::Value::String(s) => {
+            if let Some(stripped) = strip_userinfo(s) {
+                *s = stripped;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 隐藏敏感字段的值, 列表 (如 authentication 中的多个账号) 逐项隐藏
+fn redact_secret(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Null => {}
+        serde_yaml::Value::Mapping(_) => redact_value(value),
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(redact_secret),
+        _ => *value = "REDACTED".into(),
+    }
+}
+
+/// 去掉链接中的用户信息、路径和查询参数, 不是链接或无需改动时返回 None
+fn redact_url(s: &str) -> Option<String> {
+    let url = parse_url(s)?;
+    let host = url.host_str()?;
+    let base = match url.port() {
+        Some(port) => format!("{}://{}:{}", url.scheme(), host, port),
+        None => format!("{}://{}", url.scheme(), host),
+    };
+    let has_secret = !url.username().is_empty() || url.password().is_some()
+        || !matches!(url.path(), "" | "/") || url.query().is_some() || url.fragment().is_some();
+    has_secret.then(|| format!("{}/REDACTED", base))
+}
+
+/// 只去掉链接中的用户信息 (如 DoH 地址中的 user:pass@), 没有用户信息时返回 None
+fn strip_userinfo(s: &str) -> Option<String> {
+    let mut url = parse_url(s)?;
+    if url.username().is_empty() && url.password().is_none() {
+        return None;
+    }
+    url.set_username("").ok()?;
+    url.set_password(None).ok()?;
+    Some(url.to_string())
+}
+
+/// 解析带 "://" 的链接, 避免把 "host:port" 之类的值当作链接
+fn parse_url(s: &str) -> Option<reqwest::Url> {
+    s.contains("://").then(|| reqwest::Url::parse(s).ok()).flatten()
+}
+
 /// 内部列出配置函数，不打印输出
 fn list_configs_internal() -> Result<Vec<String>> {
     let mut configs = Vec::new();
@@ -95,16 +523,14 @@ fn list_configs_internal() -> Result<Vec<String>> {
     }
     
     let entries = fs::read_dir(CONFIG_DIR)?;
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "yaml" || ext == "yml" {
-                        if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-                            if name != ACTIVE_CONFIG {
-                                configs.push(name.to_string());
-                            }
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            if let Some(ext) = path.extension() {
+                if ext == "yaml" || ext == "yml" {
+                    if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+                        if is_profile_name(name) {
+                            configs.push(name.to_string());
                         }
                     }
                 }
@@ -114,6 +540,72 @@ fn list_configs_internal() -> Result<Vec<String>> {
     Ok(configs)
 }
 
+/// 若文件是通用订阅格式, 原地转换为 Clash 配置
+///
+/// 已经是 Clash YAML 的文件保持不变
+fn convert_if_needed(path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path).context("读取下载的文件失败")?;
+    if serde_yaml::from_str::<serde_yaml::Value>(&content).map(|v| v.is_mapping()).unwrap_or(false) {
+        return Ok(());
+    }
+    let Some(result) = convert::convert_subscription(&content) else {
+        return Ok(());
+    };
+    let converted = result.context("订阅转换失败")?;
+
+    println!("检测到通用订阅格式，已转换 {} 个节点。", converted.proxies.to_string().green());
+    for (link, reason) in &converted.skipped {
+        let scheme = link.split("://").next().unwrap_or(link);
+        println!("  {} 跳过 {}:// 链接: {}", "!".yellow(), scheme, reason);
+    }
+    fs::write(path, converted.yaml).context("写入转换后的配置失败")?;
+    Ok(())
+}
+
+/// 使用已安装的内核测试配置文件 (`clash -t`), 能发现未知加密方式、
+/// 引用不存在的代理组、规则语法错误等结构检查无法发现的问题
+///
+/// 在临时目录中运行并链接 /etc/clash 下的数据库文件, 避免内核下载数据或改动工作目录。
+/// 内核未安装时跳过。
+fn test_with_core(path: &Path) -> Result<()> {
+    if !Path::new(install::CLASH_BIN).exists() {
+        println!("{}", "未安装 Clash 内核，跳过内核校验。".yellow());
+        return Ok(());
+    }
+
+    let work_dir = tempfile::tempdir()?;
+    for data in ["Country.mmdb", "geosite.dat", "geoip.dat", "GeoLite2-ASN.mmdb"] {
+        let source = Path::new(CONFIG_DIR).join(data);
+        if source.exists() {
+            std::os::unix::fs::symlink(&source, work_dir.path().join(data)).ok();
+        }
+    }
+
+    let output = Command::new(install::CLASH_BIN)
+        .arg("-t")
+        .arg("-d")
+        .arg(work_dir.path())
+        .arg("-f")
+        .arg(path)
+        .output()
+        .context("运行 Clash 内核失败")?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    // 只保留内核输出中的错误行, 没有匹配时保留全部输出
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let lines: Vec<&str> = text.lines().map(|l| l.trim_end()).filter(|l| !l.is_empty()).collect();
+    let errors: Vec<&str> = lines.iter().copied()
+        .filter(|l| {
+            let lower = l.to_lowercase();
+            lower.contains("error") || lower.contains("fatal") || lower.contains("failed")
+        })
+        .collect();
+    let shown = if errors.is_empty() { lines } else { errors };
+    Err(anyhow!("Clash 内核校验未通过:\n{}", shown.iter().map(|l| format!("  {}", l)).collect::<Vec<_>>().join("\n")))
+}
+
 /// 验证配置文件格式
 fn validate_config_file(path: &Path) -> Result<()> {
     let content = fs::read_to_string(path).context("读取下载的文件失败")?;
@@ -134,12 +626,12 @@ fn validate_config_file(path: &Path) -> Result<()> {
     
     // 1. Base64 格式 (无空格，长字符串)
     if trimmed.len() > 50 && !trimmed.contains(' ') && !trimmed.contains('\n') {
-        return Err(anyhow!("检测到 Base64 编码的内容。这是通用的订阅格式，不是 Clash 配置文件。"));
+        return Err(anyhow!("检测到 Base64 编码的内容，但其中没有可转换的节点链接。"));
     }
     
     // 2. 直接的节点列表 (包含 trojan://, ss://, vmess:// 等)
     if content.contains("trojan://") || content.contains("ss://") || content.contains("vmess://") || content.contains("vless://") {
-        return Err(anyhow!("检测到原始节点列表。Clash 需要 YAML 格式的配置文件。"));
+        return Err(anyhow!("检测到原始节点列表，但其中没有受支持的协议 (ss/vmess/trojan/vless/hysteria2/tuic)。"));
     }
 
     // 3. HTML 内容 (可能是下载到了登录页或验证页)
@@ -162,30 +654,94 @@ fn list_configs() -> Result<Vec<String>> {
 
     let entries = fs::read_dir(CONFIG_DIR).context(format!("读取目录 {} 失败", CONFIG_DIR))?;
     
+    // 元信息缺失或损坏时仍然列出配置文件
+    let store = ProfileStore::load().unwrap_or_else(|e| {
+        println!("{} {:#}", "警告:".yellow(), e);
+        ProfileStore::default()
+    });
+
+    let state = store.active_state();
+    let active_name = match &state {
+        ActiveState::Clean(a) | ActiveState::Drifted(a) => a.name.clone(),
+        ActiveState::Unknown => None,
+    };
+
     println!("{} 下的可用配置:", CONFIG_DIR);
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "yaml" || ext == "yml" {
-                        if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-                             // 不列出当前激活的软链接/副本目标
-                             if name != ACTIVE_CONFIG {
-                                 configs.push(name.to_string());
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            if let Some(ext) = path.extension() {
+                if ext == "yaml" || ext == "yml" {
+                    if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
+                         // 不列出当前激活的软链接/副本目标
+                         if is_profile_name(name) {
+                             configs.push(name.to_string());
+                             if active_name.as_deref() == Some(name) {
+                                 println!("  {} {} {}", "*".green(), name.green().bold(), "(当前使用)".green());
+                             } else {
                                  println!("  - {}", name);
                              }
-                        }
+                             if let Some(profile) = store.get(name) {
+                                 print_profile_details(profile);
+                             }
+                         }
                     }
                 }
             }
         }
     }
+    if let ActiveState::Drifted(active) = &state {
+        let applied = active.applied_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+        println!("{} {} 在 {} 切换后被手动修改过，重新执行 config select 会覆盖这些修改。",
+            "注意:".yellow(), ACTIVE_CONFIG, applied);
+    }
     Ok(configs)
 }
 
+/// 打印配置来源、流量和到期信息, 即将用尽或到期时给出警告
+fn print_profile_details(profile: &Profile) {
+    let updated = profile.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    let kind = if profile.is_remote() { "订阅" } else { "本地文件" };
+    println!("      来源: {} {} (更新于 {})", kind, profile.source.dimmed(), updated);
+
+    let Some(info) = &profile.user_info else { return };
+    match info.remaining() {
+        Some(remaining) => {
+            let line = format!("已用 {} / {}, 剩余 {}",
+                format_bytes(info.used()), format_bytes(info.total), format_bytes(remaining));
+            if remaining == 0 {
+                println!("      流量: {}  {}", line, "流量已用尽".red().bold());
+            } else if (remaining as f64) < info.total as f64 * QUOTA_WARN_RATIO {
+                println!("      流量: {}  {}", line, "流量即将用尽".yellow());
+            } else {
+                println!("      流量: {}", line);
+            }
+        }
+        None => println!("      流量: 已用 {} (不限量)", format_bytes(info.used())),
+    }
+
+    if let (Some(at), Some(days)) = (profile.expire_at(), profile.days_left()) {
+        let date = at.with_timezone(&chrono::Local).format("%Y-%m-%d");
+        if at <= chrono::Utc::now() {
+            println!("      到期: {}  {}", date, "订阅已过期".red().bold());
+        } else if days <= EXPIRE_WARN_DAYS {
+            println!("      到期: {} (还剩 {} 天)  {}", date, days, "订阅即将到期".yellow());
+        } else {
+            println!("      到期: {} (还剩 {} 天)", date, days);
+        }
+    }
+}
+
 /// 交互式选择并切换配置
-fn select_config() -> Result<()> {
+async fn select_config(name: Option<String>) -> Result<()> {
+    if let Some(name) = name {
+        let (filename, _) = existing_profile(&name)?;
+        return apply_config(&filename, &mut ProfileStore::load()?).await;
+    }
+    if !console::user_attended() {
+        return Err(anyhow!("非交互环境下请指定配置名称: clash-cli config select <name>"));
+    }
+
     let configs = list_configs()?;
     if configs.is_empty() {
         println!("未找到配置文件。");
@@ -199,48 +755,489 @@ fn select_config() -> Result<()> {
         .interact()?;
         
     let selected_config = &configs[selection];
-    println!("正在切换到 {}", selected_config);
-    
-    let source = Path::new(CONFIG_DIR).join(selected_config);
+    apply_config(selected_config, &mut ProfileStore::load()?).await
+}
+
+/// 应用指定的配置文件, 切换记录写入调用方持有的 `store` 并保存
+async fn apply_config(config_name: &str, store: &mut ProfileStore) -> Result<()> {
+    let source = Path::new(CONFIG_DIR).join(config_name);
+    let content = fs::read_to_string(&source).context(format!("读取 {} 失败", source.display()))?;
+    apply_profile(config_name, &content, store).await
+}
+
+/// 以 `content` 作为配置 `config_name` 的内容应用, 不读取配置目录中的文件
+async fn apply_profile(config_name: &str, content: &str, store: &mut ProfileStore) -> Result<()> {
+    println!("正在切换到 {}", config_name);
     let target = Path::new(CONFIG_DIR).join(ACTIVE_CONFIG);
-    
-    let status = Command::new("sudo")
-        .arg("cp")
-        .arg(source)
-        .arg(target)
-        .status()?;
-        
-    if !status.success() {
-        return Err(anyhow!("切换配置失败"));
+
+    // 合并本地覆盖文件后生成新的 config.yaml
+    let temp_dir = tempfile::tempdir()?;
+    let rendered = temp_dir.path().join(ACTIVE_CONFIG);
+    fs::write(&rendered, render_profile(content)?)?;
+
+    let restart_reason = tun_change_reason(&target, &rendered);
+    switch_active_config(&rendered, restart_reason).await?;
+    record_active(store, |store, content| store.set_active(Some(config_name), content));
+    println!("{}", "配置已应用。".green());
+    Ok(())
+}
+
+/// 当前使用的配置: 优先使用切换记录, 没有记录时按内容查找
+fn current_profile() -> Option<String> {
+    match ProfileStore::load().ok()?.active {
+        Some(active) => active.name,
+        None => find_active_profile(load_override().ok()?.as_ref()),
+    }
+}
+
+/// 写入 config.yaml 成功后更新切换记录并保存, 失败只给出警告
+///
+/// 使用调用方已加载的 `store`, 避免调用方随后保存旧副本时覆盖新的记录
+pub fn record_active(store: &mut ProfileStore, update: impl FnOnce(&mut ProfileStore, &str)) {
+    let result = fs::read_to_string(Path::new(CONFIG_DIR).join(ACTIVE_CONFIG))
+        .map_err(anyhow::Error::from)
+        .and_then(|content| {
+            update(store, &content);
+            store.save()
+        });
+    if let Err(e) = result {
+        println!("{} {:#}", "警告: 保存切换记录失败:".yellow(), e);
+    }
+}
+
+/// 是否为普通配置文件 (排除 config.yaml 和覆盖文件)
+fn is_profile_name(name: &str) -> bool {
+    name != ACTIVE_CONFIG && Path::new(merge::OVERRIDE_FILE).file_name().is_some_and(|f| f != name)
+}
+
+/// 读取本地覆盖文件, 不存在或为空时返回 None
+fn load_override() -> Result<Option<serde_yaml::Mapping>> {
+    match fs::read_to_string(merge::OVERRIDE_FILE) {
+        Ok(content) => merge::parse_override(&content).context(format!("{} 格式错误", merge::OVERRIDE_FILE)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// 生成 config.yaml 的内容: 配置文件合并本地覆盖
+fn render_profile(content: &str) -> Result<String> {
+    render_with(content, load_override()?.as_ref())
+}
+
+/// 使用指定的覆盖内容生成 config.yaml
+///
+/// 没有覆盖内容时原样返回, 保留配置文件中的注释和格式
+fn render_with(content: &str, overrides: Option<&serde_yaml::Mapping>) -> Result<String> {
+    let Some(overrides) = overrides else {
+        return Ok(content.to_string());
+    };
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(content).context("解析配置文件失败")?;
+    merge::apply_override(&mut doc, overrides)?;
+    Ok(format!("# 由 clash-cli 生成, 已合并 {}\n{}", merge::OVERRIDE_FILE, serde_yaml::to_string(&doc)?))
+}
+
+/// 查找按给定覆盖内容生成后与当前 config.yaml 一致的配置文件
+fn find_active_profile(overrides: Option<&serde_yaml::Mapping>) -> Option<String> {
+    let current = fs::read_to_string(Path::new(CONFIG_DIR).join(ACTIVE_CONFIG)).ok()?;
+    list_configs_internal().ok()?.into_iter().find(|name| {
+        fs::read_to_string(Path::new(CONFIG_DIR).join(name)).ok()
+            .and_then(|content| render_with(&content, overrides).ok())
+            .is_some_and(|rendered| rendered == current)
+    })
+}
+
+/// 使用编辑器修改覆盖文件, 保存前检查格式
+async fn edit_override() -> Result<()> {
+    let temp = tempfile::Builder::new().suffix(".yaml").tempfile()?;
+    let original = fs::read_to_string(merge::OVERRIDE_FILE)
+        .unwrap_or_else(|_| merge::OVERRIDE_TEMPLATE.to_string());
+    fs::write(temp.path(), &original)?;
+
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    loop {
+        // 编辑器命令可能带参数, 如 "code --wait"
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(temp.path())
+            .status()
+            .context(format!("启动编辑器 {} 失败", editor))?;
+        if !status.success() {
+            return Err(anyhow!("编辑器异常退出，未保存修改"));
+        }
+
+        let edited = fs::read_to_string(temp.path())?;
+        if edited == original {
+            println!("覆盖文件未修改。");
+            return Ok(());
+        }
+        match merge::parse_override(&edited) {
+            Ok(_) => break,
+            Err(e) => {
+                println!("{} {:#}", "覆盖文件格式错误:".red(), e);
+                let retry = dialoguer::Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt("是否重新编辑?")
+                    .default(true)
+                    .interact()?;
+                if !retry {
+                    return Err(anyhow!("已放弃修改"));
+                }
+            }
+        }
+    }
+
+    install_file(temp.path(), Path::new(merge::OVERRIDE_FILE))?;
+    println!("{}", "覆盖文件已保存。".green());
+
+    // 没有切换记录时, 按修改前的覆盖内容查找当前使用的配置
+    let previous = merge::parse_override(&original).ok().flatten();
+    let mut store = ProfileStore::load()?;
+    let active = store.active.as_ref()
+        .and_then(|a| a.name.clone())
+        .or_else(|| find_active_profile(previous.as_ref()));
+    match active {
+        Some(name) => {
+            println!("正在重新应用当前配置 {}...", name);
+            apply_config(&name, &mut store).await?;
+        }
+        None => println!("使用 clash-cli config select 重新应用配置后生效。"),
     }
-    
-    println!("正在重启 Clash 服务...");
-    Command::new("sudo").arg("systemctl").arg("restart").arg("clash").status()?;
-    
-    println!("{}", "配置已切换并重启服务。".green());
     Ok(())
 }
 
-/// 应用指定的配置文件
-fn apply_config(config_name: &str) -> Result<()> {
-    println!("正在切换到 {}", config_name);
-    
-    let source = Path::new(CONFIG_DIR).join(config_name);
+/// 显示覆盖文件
+fn show_override() -> Result<()> {
+    match fs::read_to_string(merge::OVERRIDE_FILE) {
+        Ok(content) => {
+            println!("{}", merge::OVERRIDE_FILE.dimmed());
+            print!("{}", content);
+        }
+        Err(_) => println!("尚未创建覆盖文件，使用 clash-cli config override edit 创建。"),
+    }
+    Ok(())
+}
+
+/// 解析 diff 的参数: 包含路径分隔符时视为文件路径, 否则视为配置名称
+fn diff_target(name: &str) -> Result<(String, PathBuf)> {
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        if !path.is_file() {
+            return Err(anyhow!("文件 {} 不存在", name));
+        }
+        return Ok((name.to_string(), path));
+    }
+    existing_profile(name)
+}
+
+/// 按语义比较两份配置, b 省略时与当前生效的 config.yaml 比较
+fn diff_configs(a: &str, b: Option<&str>) -> Result<()> {
+    let (old_name, old_path) = diff_target(a)?;
+    let (new_name, new_path) = diff_target(b.unwrap_or(ACTIVE_CONFIG))?;
+    let parse = |name: &str, path: &Path| -> Result<serde_yaml::Value> {
+        let content = fs::read_to_string(path).context(format!("读取 {} 失败", name))?;
+        serde_yaml::from_str(&content).context(format!("解析 {} 失败", name))
+    };
+    let old = parse(&old_name, &old_path)?;
+    let new = parse(&new_name, &new_path)?;
+
+    println!("{}", format!("--- {}", old_name).red());
+    println!("{}", format!("+++ {}", new_name).green());
+    let sections = diff::diff_configs(&old, &new);
+    if sections.is_empty() {
+        println!("\n两份配置没有差异。");
+        return Ok(());
+    }
+    for section in sections {
+        println!("\n{} ({} 处)", section.title.bold(), section.changes.len());
+        for change in section.changes {
+            match change {
+                diff::Change::Added(line) => println!("  {} {}", "+".green(), line),
+                diff::Change::Removed(line) => println!("  {} {}", "-".red(), line),
+                diff::Change::Modified(line) => println!("  {} {}", "~".yellow(), line),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 显示覆盖文件对指定配置的改动
+fn diff_override(name: Option<String>) -> Result<()> {
+    let name = match name {
+        Some(n) => profile_filename(&n)?,
+        None => current_profile()
+            .ok_or_else(|| anyhow!("无法确定当前使用的配置，请指定配置名称"))?,
+    };
+    let content = fs::read_to_string(Path::new(CONFIG_DIR).join(&name))
+        .context(format!("读取配置 {} 失败", name))?;
+    let rendered = render_profile(&content)?;
+    if rendered == content {
+        println!("覆盖文件没有改动 {}。", name);
+        return Ok(());
+    }
+
+    // 统一格式后再比较, 只显示覆盖带来的改动
+    let normalized = serde_yaml::from_str::<serde_yaml::Value>(&content)
+        .and_then(|doc| serde_yaml::to_string(&doc))
+        .context("解析配置文件失败")?;
+    let rendered_body = rendered.split_once('\n').map(|(_, body)| body).unwrap_or(&rendered);
+    let diff = similar::TextDiff::from_lines(normalized.as_str(), rendered_body);
+    println!("{}", format!("--- {}", name).red());
+    println!("{}", format!("+++ {} + {}", name, merge::OVERRIDE_FILE).green());
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        println!("{}", hunk.header().to_string().cyan());
+        for change in hunk.iter_changes() {
+            let line = change.to_string_lossy();
+            let line = line.trim_end_matches('\n');
+            match change.tag() {
+                similar::ChangeTag::Delete => println!("{}", format!("-{}", line).red()),
+                similar::ChangeTag::Insert => println!("{}", format!("+{}", line).green()),
+                similar::ChangeTag::Equal => println!(" {}", line),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 将新配置写入 config.yaml 并使其生效, 失败时自动回滚
+///
+/// 写入前先用内核测试新配置并备份当前的 config.yaml, 写入使用先复制再重命名的方式保证原子性。
+/// 热重载或重启后 Clash 未能在限定时间内恢复正常时, 恢复备份并重启服务。
+pub async fn switch_active_config(source: &Path, restart_reason: Option<&str>) -> Result<()> {
     let target = Path::new(CONFIG_DIR).join(ACTIVE_CONFIG);
-    
+    test_with_core(source)?;
+
+    // 写入新配置前连接当前运行的实例, 控制器地址可能随配置改变
+    let client = ClashClient::new();
+    let backup = backup_active_config()?;
+    install_file_atomic(source, &target)?;
+    // 安装完成后再清理旧备份, 回滚时 source 可能正是最旧的备份
+    prune_backups();
+
+    let result = match proxy::reload_or_restart(&client, restart_reason).await {
+        Ok(_) => proxy::wait_healthy(HEALTH_TIMEOUT).await,
+        Err(e) => Err(e),
+    };
+    let Err(e) = result else { return Ok(()) };
+
+    let Some(backup) = backup else {
+        return Err(e.context("新配置未能正常运行，且没有可恢复的备份"));
+    };
+    println!("{} {:#}", "新配置未能正常运行:".red(), e);
+    println!("正在恢复之前的配置 ({})...", backup.display());
+    install_file_atomic(&backup, &target)?;
+    proxy::reload_or_restart(&ClashClient::new(), Some("回滚配置")).await?;
+    match proxy::wait_healthy(HEALTH_TIMEOUT).await {
+        Ok(()) => Err(anyhow!("切换失败，已恢复之前的配置: {:#}", e)),
+        Err(restore_err) => Err(anyhow!("切换失败，且恢复之前的配置后 Clash 仍未正常运行: {:#}", restore_err)),
+    }
+}
+
+/// 通过 sudo 先复制到同目录的临时文件, 再重命名覆盖目标文件
+fn install_file_atomic(source: &Path, target: &Path) -> Result<()> {
+    let staging = target.with_extension("yaml.tmp");
+    install_file(source, &staging)?;
     let status = Command::new("sudo")
-        .arg("cp")
-        .arg(source)
+        .arg("mv")
+        .arg("-f")
+        .arg(&staging)
         .arg(target)
         .status()?;
-        
     if !status.success() {
-        return Err(anyhow!("切换配置失败"));
+        return Err(anyhow!("替换 {} 失败", target.display()));
     }
-    
-    println!("正在重启 Clash 服务...");
-    Command::new("sudo").arg("systemctl").arg("restart").arg("clash").status()?;
-    
-    println!("{}", "配置已应用并重启服务。".green());
     Ok(())
 }
+
+/// 备份当前的 config.yaml
+///
+/// config.yaml 不存在时返回 None
+fn backup_active_config() -> Result<Option<PathBuf>> {
+    let active = Path::new(CONFIG_DIR).join(ACTIVE_CONFIG);
+    if !active.exists() {
+        return Ok(None);
+    }
+    let status = Command::new("sudo").arg("mkdir").arg("-p").arg(BACKUP_DIR).status()?;
+    if !status.success() {
+        return Err(anyhow!("创建备份目录 {} 失败", BACKUP_DIR));
+    }
+
+    // 时间戳精确到毫秒, 同一时刻已有备份时追加序号, 避免相互覆盖
+    let stamp = chrono::Local::now().format(BACKUP_TIME_FORMAT).to_string();
+    let backup = (1..)
+        .map(|i| match i {
+            1 => format!("config-{}.yaml", stamp),
+            n => format!("config-{}_{}.yaml", stamp, n),
+        })
+        .map(|name| Path::new(BACKUP_DIR).join(name))
+        .find(|path| !path.exists())
+        .expect("备份文件名序号耗尽");
+    install_file(&active, &backup).context("备份当前配置失败")?;
+    Ok(Some(backup))
+}
+
+/// 清理超出数量上限的旧备份
+fn prune_backups() {
+    for old in list_backups().into_iter().skip(MAX_BACKUPS) {
+        Command::new("sudo").arg("rm").arg("-f").arg(old).status().ok();
+    }
+}
+
+/// 列出 config.yaml 的备份, 最新的在前
+fn list_backups() -> Vec<PathBuf> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(BACKUP_DIR).into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "yaml"))
+        .collect();
+    // 文件名包含时间戳, 按名称倒序即为时间倒序
+    backups.sort_by(|a, b| b.cmp(a));
+    backups
+}
+
+/// 列出或恢复 config.yaml 的历史版本
+async fn rollback(backup: Option<String>, list: bool) -> Result<()> {
+    let backups = list_backups();
+    if backups.is_empty() {
+        println!("没有可用的备份。切换配置时会自动在 {} 中保存备份。", BACKUP_DIR);
+        return Ok(());
+    }
+
+    let labels: Vec<String> = backups.iter().map(|p| describe_backup(p)).collect();
+    if list {
+        println!("{} 中的备份 (最新的在前):", BACKUP_DIR);
+        for (i, label) in labels.iter().enumerate() {
+            println!("  {:>2}. {}", i + 1, label);
+        }
+        return Ok(());
+    }
+
+    let chosen = match backup {
+        Some(key) => {
+            let by_index = key.parse::<usize>().ok()
+                .and_then(|i| i.checked_sub(1))
+                .and_then(|i| backups.get(i));
+            by_index
+                .or_else(|| backups.iter().find(|p| p.file_name().is_some_and(|n| n.to_string_lossy() == key)))
+                .ok_or_else(|| anyhow!("未找到备份 {}，使用 config rollback --list 查看可用备份", key))?
+                .clone()
+        }
+        None => {
+            let selection = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("请选择要恢复的配置版本")
+                .default(0)
+                .items(&labels)
+                .interact()?;
+            backups[selection].clone()
+        }
+    };
+
+    println!("正在恢复 {}", chosen.display());
+    let mut store = ProfileStore::load()?;
+    let active = Path::new(CONFIG_DIR).join(ACTIVE_CONFIG);
+    let restart_reason = tun_change_reason(&active, &chosen);
+    switch_active_config(&chosen, restart_reason).await?;
+    // 备份可能来自任意配置, 按内容重新确定来源
+    let name = load_override().ok().and_then(|ov| find_active_profile(ov.as_ref()));
+    record_active(&mut store, |store, content| store.set_active(name.as_deref(), content));
+    println!("{}", "已恢复到所选的配置版本。".green());
+    Ok(())
+}
+
+/// 备份的显示信息: 文件名、时间与节点数
+fn describe_backup(path: &Path) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    // 同一时刻的备份带 _<序号> 后缀, 旧版本的备份名只精确到秒
+    let stamp = name.strip_prefix("config-").and_then(|n| n.strip_suffix(".yaml")).unwrap_or_default();
+    let parse = |len: usize, format: &str| stamp.get(..len)
+        .and_then(|t| chrono::NaiveDateTime::parse_from_str(t, format).ok());
+    let time = parse(19, BACKUP_TIME_FORMAT)
+        .or_else(|| parse(15, "%Y%m%d-%H%M%S"))
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+    let proxies = fs::read_to_string(path).ok()
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok())
+        .and_then(|d| d.get("proxies").and_then(|p| p.as_sequence()).map(|p| p.len()))
+        .map(|n| format!("{} 个节点", n))
+        .unwrap_or_else(|| "无法解析".to_string());
+    format!("{}  {}  ({})", name, time.dimmed(), proxies)
+}
+
+/// 比较新旧配置的 TUN 设置, 判断是否需要重启服务
+fn tun_change_reason(old: &Path, new: &Path) -> Option<&'static str> {
+    let read = |path: &Path| fs::read_to_string(path).ok()
+        .and_then(|c| serde_yaml::from_str::<serde_yaml::Value>(&c).ok());
+    let (old, new) = (read(old)?, read(new)?);
+    tun::restart_reason(&old, &new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_provider_urls() {
+        let cases = [
+            ("https://sub.example.com/api/v1/client?token=abc", Some("https://sub.example.com/REDACTED")),
+            ("https://user:pw@sub.example.com:8443/sub", Some("https://sub.example.com:8443/REDACTED")),
+            ("https://sub.example.com", None),
+            ("https://sub.example.com/", None),
+            ("127.0.0.1:9090", None),
+            ("not a url", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(redact_url(input).as_deref(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn strips_only_userinfo_from_other_urls() {
+        let cases = [
+            ("https://user:pw@dns.example.com/dns-query", Some("https://dns.example.com/dns-query")),
+            ("https://token@dns.example.com/dns-query?x=1", Some("https://dns.example.com/dns-query?x=1")),
+            ("http://www.gstatic.com/generate_204", None),
+            ("tls://1.1.1.1:853", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(strip_userinfo(input).as_deref(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn redacts_exported_config() {
+        let mut doc: serde_yaml::Value = serde_yaml::from_str(r#"
+Secret: abc
+authentication: ["user1:pass1", "user2:pass2"]
+dns:
+  nameserver: ["https://user:pw@dns.example.com/dns-query", "8.8.8.8"]
+proxies:
+  - {name: a, type: ss, server: s, port: 1, Password: p, private-key-passphrase: q}
+proxy-groups:
+  - {name: Auto, type: url-test, url: "http://www.gstatic.com/generate_204", proxies: [a]}
+proxy-providers:
+  p1:
+    url: "https://sub.example.com/api?token=abc"
+    health-check: {enable: true, url: "https://cp.cloudflare.com/generate_204"}
+rule-providers:
+  r1: {type: http, behavior: domain, url: "https://rules.example.com/u/key/reject.yaml"}
+"#).unwrap();
+        redact_value(&mut doc);
+        let expected: serde_yaml::Value = serde_yaml::from_str(r#"
+Secret: REDACTED
+authentication: [REDACTED, REDACTED]
+dns:
+  nameserver: ["https://dns.example.com/dns-query", "8.8.8.8"]
+proxies:
+  - {name: a, type: ss, server: s, port: 1, Password: REDACTED, private-key-passphrase: REDACTED}
+proxy-groups:
+  - {name: Auto, type: url-test, url: "http://www.gstatic.com/generate_204", proxies: [a]}
+proxy-providers:
+  p1:
+    url: "https://sub.example.com/REDACTED"
+    health-check: {enable: true, url: "https://cp.cloudflare.com/generate_204"}
+rule-providers:
+  r1: {type: http, behavior: domain, url: "https://rules.example.com/REDACTED"}
+"#).unwrap();
+        assert_eq!(doc, expected);
+    }
+}
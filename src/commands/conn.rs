@@ -0,0 +1,182 @@
+use anyhow::{Result, anyhow};
+use crate::cli::{ConnAction, ConnFilter};
+use crate::clash::api::{ClashClient, Connection};
+use crate::utils::format::{format_bytes, format_duration, pad, pad_left};
+use chrono::Utc;
+use colored::*;
+
+/// 连接管理命令入口
+pub async fn run(action: ConnAction) -> Result<()> {
+    let client = ClashClient::new();
+    match action {
+        ConnAction::List { filter } => list_connections(&client, &filter).await?,
+        ConnAction::Close { id } => close_connection(&client, &id).await?,
+        ConnAction::CloseAll { filter } => close_all(&client, &filter).await?,
+    }
+    Ok(())
+}
+
+/// 判断连接是否满足过滤条件
+pub fn matches(filter: &ConnFilter, conn: &Connection) -> bool {
+    let contains = |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+
+    if let Some(host) = &filter.host {
+        if !contains(&conn.metadata.host, host) && !contains(&conn.metadata.destination_ip, host) {
+            return false;
+        }
+    }
+    if let Some(rule) = &filter.rule {
+        if !contains(&conn.rule_display(), rule) {
+            return false;
+        }
+    }
+    if let Some(chain) = &filter.chain {
+        if !conn.chains.iter().any(|c| contains(c, chain)) {
+            return false;
+        }
+    }
+    if let Some(process) = &filter.process {
+        if !contains(&conn.metadata.process_path, process) {
+            return false;
+        }
+    }
+    true
+}
+
+fn is_empty_filter(filter: &ConnFilter) -> bool {
+    filter.host.is_none() && filter.rule.is_none() && filter.chain.is_none() && filter.process.is_none()
+}
+
+/// 列出活跃连接
+async fn list_connections(client: &ClashClient, filter: &ConnFilter) -> Result<()> {
+    let snapshot = client.get_connections().await?;
+    let mut conns: Vec<&Connection> = snapshot.connections.iter()
+        .filter(|c| matches(filter, c))
+        .collect();
+    // 最新的连接排在前面
+    conns.sort_by_key(|c| std::cmp::Reverse(c.start));
+
+    println!("累计上传: {}  累计下载: {}  活跃连接: {}",
+        format_bytes(snapshot.upload_total).green(),
+        format_bytes(snapshot.download_total).green(),
+        snapshot.connections.len().to_string().cyan());
+
+    if conns.is_empty() {
+        println!("没有匹配的连接。");
+        return Ok(());
+    }
+
+    println!("{}  {}  {}  {}  {}  {}  {}  {}  进程",
+        pad("ID", 8), pad("网络", 4), pad("目标", 40), pad("代理链", 30), pad("规则", 24),
+        pad_left("上传", 9), pad_left("下载", 9), pad_left("时长", 7));
+    let now = Utc::now();
+    for conn in &conns {
+        println!("{}  {}  {}  {}  {}  {:>9}  {:>9}  {:>7}  {}",
+            pad(&short_id(&conn.id).dimmed().to_string(), 8),
+            pad(&conn.metadata.network, 4),
+            pad(&conn.destination().cyan().to_string(), 40),
+            pad(&conn.chain_display().yellow().to_string(), 30),
+            pad(&conn.rule_display(), 24),
+            format_bytes(conn.upload),
+            format_bytes(conn.download),
+            format_duration((now - conn.start).num_seconds()),
+            conn.process_name());
+    }
+    if conns.len() != snapshot.connections.len() {
+        println!("共 {} 条匹配连接。", conns.len());
+    }
+    Ok(())
+}
+
+/// 关闭单个连接, 支持使用 ID 前缀
+async fn close_connection(client: &ClashClient, id: &str) -> Result<()> {
+    let snapshot = client.get_connections().await?;
+    let candidates: Vec<&Connection> = snapshot.connections.iter()
+        .filter(|c| c.id.starts_with(id))
+        .collect();
+
+    let conn = match candidates.as_slice() {
+        [conn] => *conn,
+        [] => return Err(anyhow!("未找到 ID 为 {} 的连接", id)),
+        _ => return Err(anyhow!("ID 前缀 {} 匹配到 {} 条连接，请提供更长的 ID", id, candidates.len())),
+    };
+
+    client.close_connection(&conn.id).await?;
+    println!("已关闭连接 {} ({})", short_id(&conn.id), conn.destination().green());
+    Ok(())
+}
+
+/// 关闭所有 (或匹配过滤条件的) 连接
+async fn close_all(client: &ClashClient, filter: &ConnFilter) -> Result<()> {
+    if is_empty_filter(filter) {
+        client.close_all_connections().await?;
+        println!("{}", "已关闭所有连接。".green());
+        return Ok(());
+    }
+
+    let snapshot = client.get_connections().await?;
+    let mut closed = 0;
+    for conn in snapshot.connections.iter().filter(|c| matches(filter, c)) {
+        client.close_connection(&conn.id).await?;
+        closed += 1;
+    }
+    println!("已关闭 {} 条匹配的连接。", closed.to_string().green());
+    Ok(())
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection() -> Connection {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "metadata": {
+                "network": "tcp",
+                "type": "Tun",
+                "host": "www.Google.com",
+                "destinationIP": "142.250.1.1",
+                "destinationPort": "443",
+                "processPath": "/usr/bin/curl",
+            },
+            "start": "2026-01-01T00:00:00Z",
+            "chains": ["HK 01", "Proxy"],
+            "rule": "DomainSuffix",
+            "rulePayload": "google.com",
+        })).unwrap()
+    }
+
+    fn filter(host: Option<&str>, rule: Option<&str>, chain: Option<&str>, process: Option<&str>) -> ConnFilter {
+        let own = |s: Option<&str>| s.map(str::to_string);
+        ConnFilter { host: own(host), rule: own(rule), chain: own(chain), process: own(process) }
+    }
+
+    #[test]
+    fn matches_connections() {
+        let conn = connection();
+        let cases = [
+            (filter(None, None, None, None), true),
+            (filter(Some("google"), None, None, None), true),
+            (filter(Some("142.250"), None, None, None), true),
+            (filter(Some("github"), None, None, None), false),
+            (filter(None, Some("domainsuffix(google"), None, None), true),
+            (filter(None, Some("MATCH"), None, None), false),
+            (filter(None, None, Some("hk"), None), true),
+            (filter(None, None, Some("proxy"), None), true),
+            (filter(None, None, Some("US"), None), false),
+            (filter(None, None, None, Some("curl")), true),
+            (filter(None, None, None, Some("/usr/bin")), true),
+            (filter(None, None, None, Some("wget")), false),
+            // 多个条件需要同时满足
+            (filter(Some("google"), None, Some("HK"), Some("curl")), true),
+            (filter(Some("google"), None, Some("HK"), Some("wget")), false),
+        ];
+        for (filter, expected) in cases {
+            assert_eq!(matches(&filter, &conn), expected, "{:?}", filter);
+        }
+    }
+}
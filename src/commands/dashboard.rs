@@ -1,20 +1,23 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{Event, KeyCode, KeyEvent, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Line},
-    widgets::{Block, Borders, List, ListItem, Sparkline},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, TableState, Tabs},
     Terminal,
 };
-use std::{io, time::Duration};
+use std::{collections::{HashMap, HashSet}, io, time::{Duration, Instant}};
 use tokio::sync::mpsc;
-use crate::clash::api::{ClashClient, Traffic};
+use crate::clash::api::{ClashClient, Connection, DelayTest, ProxyItem, Traffic};
+use crate::commands::node::test_delays;
+use crate::utils::format::{format_bytes, format_duration};
+use chrono::Utc;
 use futures_util::StreamExt;
 
 #[derive(Debug)]
@@ -23,14 +26,159 @@ enum AppEvent {
     Tick,
     Traffic(Traffic),
     Log(String),
-    Proxies(Vec<(String, String, String)>), // Name, Now, Delay
+    Proxies(Vec<GroupView>),
+    Connections(Vec<ConnRow>),
+    Status(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Overview,
+    Connections,
+    Proxies,
+}
+
+/// A proxy group with its members and their latest delays
+#[derive(Debug, Clone)]
+struct GroupView {
+    name: String,
+    group_type: String,
+    now: String,
+    /// Latest delay of the selected member, 0 = no data / failed
+    delay: u64,
+    members: Vec<MemberView>,
+}
+
+#[derive(Debug, Clone)]
+struct MemberView {
+    name: String,
+    delay: u64,
+}
+
+/// A row of the flattened group/member list on the proxies tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProxyRow {
+    Group(usize),
+    Member(usize, usize),
+}
+
+/// Latest recorded delay of a proxy, 0 when there is no data
+fn last_delay(proxies: &HashMap<String, ProxyItem>, name: &str) -> u64 {
+    proxies.get(name)
+        .and_then(|p| p.history.as_ref())
+        .and_then(|h| h.last())
+        .map(|h| h.delay)
+        .unwrap_or(0)
+}
+
+/// Build the group list (GLOBAL first, then by name) from the /proxies response
+fn build_groups(proxies: &HashMap<String, ProxyItem>) -> Vec<GroupView> {
+    let mut groups: Vec<GroupView> = proxies.iter()
+        .filter(|(_, item)| item.proxy_type == "Selector" || item.proxy_type == "URLTest" || item.proxy_type == "Fallback")
+        .map(|(name, item)| {
+            let now = item.now.clone().unwrap_or_default();
+            let delay = match last_delay(proxies, &now) {
+                0 => last_delay(proxies, name),
+                d => d,
+            };
+            let members = item.all.iter().flatten()
+                .map(|m| MemberView { name: m.clone(), delay: last_delay(proxies, m) })
+                .collect();
+            GroupView { name: name.clone(), group_type: item.proxy_type.clone(), now, delay, members }
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        if a.name == "GLOBAL" { std::cmp::Ordering::Less }
+        else if b.name == "GLOBAL" { std::cmp::Ordering::Greater }
+        else { a.name.cmp(&b.name) }
+    });
+    groups
+}
+
+fn delay_style(delay: u64) -> Style {
+    if delay > 0 && delay < 500 { Style::default().fg(Color::Green) }
+    else if delay == 0 { Style::default().fg(Color::Red) }
+    else { Style::default().fg(Color::Yellow) }
+}
+
+/// Sort column of the connections table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnSort {
+    Speed,
+    Total,
+    Age,
+    Host,
+    Chain,
+    Rule,
+}
+
+impl ConnSort {
+    fn next(self) -> Self {
+        match self {
+            ConnSort::Speed => ConnSort::Total,
+            ConnSort::Total => ConnSort::Age,
+            ConnSort::Age => ConnSort::Host,
+            ConnSort::Host => ConnSort::Chain,
+            ConnSort::Chain => ConnSort::Rule,
+            ConnSort::Rule => ConnSort::Speed,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ConnSort::Speed => "速度",
+            ConnSort::Total => "流量",
+            ConnSort::Age => "时长",
+            ConnSort::Host => "主机",
+            ConnSort::Chain => "代理链",
+            ConnSort::Rule => "规则",
+        }
+    }
+}
+
+/// A connection together with its speed since the previous poll
+#[derive(Debug, Clone)]
+struct ConnRow {
+    conn: Connection,
+    up_speed: u64,
+    down_speed: u64,
+}
+
+impl ConnRow {
+    /// Case-insensitive keyword match against host, chain, rule and process
+    fn matches(&self, keyword: &str) -> bool {
+        if keyword.is_empty() {
+            return true;
+        }
+        let keyword = keyword.to_lowercase();
+        [
+            self.conn.destination(),
+            self.conn.chain_display(),
+            self.conn.rule_display(),
+            self.conn.metadata.process_path.clone(),
+        ]
+        .iter()
+        .any(|field| field.to_lowercase().contains(&keyword))
+    }
 }
 
 struct App {
     logs: Vec<String>,
     traffic_up: Vec<u64>,
     traffic_down: Vec<u64>,
-    proxies: Vec<(String, String, String)>,
+    proxies: Vec<GroupView>,
+    expanded: HashSet<String>,
+    proxy_state: ListState,
+    tab: Tab,
+    conns: Vec<ConnRow>,
+    conn_filter: String,
+    filter_editing: bool,
+    /// `X` was pressed without a filter; waiting for `y` before closing every connection
+    confirm_close_all: bool,
+    conn_sort: ConnSort,
+    sort_desc: bool,
+    conn_state: TableState,
+    status: Option<String>,
     should_quit: bool,
 }
 
@@ -41,10 +189,139 @@ impl App {
             traffic_up: vec![0; 300],
             traffic_down: vec![0; 300],
             proxies: Vec::new(),
+            expanded: HashSet::new(),
+            proxy_state: ListState::default(),
+            tab: Tab::Overview,
+            conns: Vec::new(),
+            conn_filter: String::new(),
+            filter_editing: false,
+            confirm_close_all: false,
+            conn_sort: ConnSort::Speed,
+            sort_desc: true,
+            conn_state: TableState::default(),
+            status: None,
             should_quit: false,
         }
     }
 
+    /// Connections matching the filter, in display order
+    fn visible_conns(&self) -> Vec<&ConnRow> {
+        let mut rows: Vec<&ConnRow> = self.conns.iter()
+            .filter(|r| r.matches(&self.conn_filter))
+            .collect();
+        rows.sort_by(|a, b| {
+            let ord = match self.conn_sort {
+                ConnSort::Speed => (a.up_speed + a.down_speed).cmp(&(b.up_speed + b.down_speed)),
+                ConnSort::Total => (a.conn.upload + a.conn.download).cmp(&(b.conn.upload + b.conn.download)),
+                ConnSort::Age => b.conn.start.cmp(&a.conn.start),
+                ConnSort::Host => a.conn.destination().cmp(&b.conn.destination()),
+                ConnSort::Chain => a.conn.chain_display().cmp(&b.conn.chain_display()),
+                ConnSort::Rule => a.conn.rule_display().cmp(&b.conn.rule_display()),
+            };
+            if self.sort_desc { ord.reverse() } else { ord }
+        });
+        rows
+    }
+
+    fn selected_conn_id(&self) -> Option<String> {
+        let index = self.conn_state.selected()?;
+        self.visible_conns().get(index).map(|r| r.conn.id.clone())
+    }
+
+    /// Re-select the given connection after the list changed, keeping the highlight on it
+    fn reselect_conn(&mut self, id: Option<String>) {
+        let visible: Vec<String> = self.visible_conns().iter().map(|r| r.conn.id.clone()).collect();
+        let index = id
+            .and_then(|id| visible.iter().position(|v| *v == id))
+            .or_else(|| self.conn_state.selected().map(|i| i.min(visible.len().saturating_sub(1))));
+        self.conn_state.select(if visible.is_empty() { None } else { Some(index.unwrap_or(0)) });
+    }
+
+    fn on_connections(&mut self, rows: Vec<ConnRow>) {
+        let selected = self.selected_conn_id();
+        self.conns = rows;
+        self.reselect_conn(selected);
+    }
+
+    /// Groups and, for expanded groups, their members in display order
+    fn proxy_rows(&self) -> Vec<ProxyRow> {
+        let mut rows = Vec::new();
+        for (gi, group) in self.proxies.iter().enumerate() {
+            rows.push(ProxyRow::Group(gi));
+            if self.expanded.contains(&group.name) {
+                rows.extend((0..group.members.len()).map(|mi| ProxyRow::Member(gi, mi)));
+            }
+        }
+        rows
+    }
+
+    fn selected_proxy_row(&self) -> Option<ProxyRow> {
+        self.proxy_rows().get(self.proxy_state.selected()?).copied()
+    }
+
+    /// Group name and, for a member row, member name of the highlighted row
+    fn selected_proxy_names(&self) -> Option<(String, Option<String>)> {
+        match self.selected_proxy_row()? {
+            ProxyRow::Group(gi) => Some((self.proxies[gi].name.clone(), None)),
+            ProxyRow::Member(gi, mi) => {
+                let group = &self.proxies[gi];
+                Some((group.name.clone(), Some(group.members[mi].name.clone())))
+            }
+        }
+    }
+
+    /// Move the highlight to the row matching the given names, if still present
+    fn reselect_proxy(&mut self, names: Option<(String, Option<String>)>) {
+        let rows = self.proxy_rows();
+        let index = names.and_then(|(group, member)| rows.iter().position(|row| match (*row, &member) {
+            (ProxyRow::Group(gi), None) => self.proxies[gi].name == group,
+            (ProxyRow::Member(gi, mi), Some(m)) => self.proxies[gi].name == group && self.proxies[gi].members[mi].name == *m,
+            _ => false,
+        }));
+        let index = index.or_else(|| self.proxy_state.selected().map(|i| i.min(rows.len().saturating_sub(1))));
+        self.proxy_state.select(if rows.is_empty() { None } else { Some(index.unwrap_or(0)) });
+    }
+
+    fn on_proxies(&mut self, groups: Vec<GroupView>) {
+        let selected = self.selected_proxy_names();
+        self.proxies = groups;
+        self.reselect_proxy(selected);
+    }
+
+    fn move_proxy_selection(&mut self, delta: isize) {
+        let len = self.proxy_rows().len();
+        if len == 0 {
+            self.proxy_state.select(None);
+            return;
+        }
+        let current = self.proxy_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len as isize - 1);
+        self.proxy_state.select(Some(next as usize));
+    }
+
+    /// Expand or collapse the group of the highlighted row
+    fn set_expanded(&mut self, expand: bool) {
+        let Some((group, _)) = self.selected_proxy_names() else { return };
+        if expand {
+            self.expanded.insert(group.clone());
+        } else {
+            self.expanded.remove(&group);
+        }
+        // Collapsing from a member row moves the highlight back to its group
+        self.reselect_proxy(Some((group, None)));
+    }
+
+    fn move_conn_selection(&mut self, delta: isize) {
+        let len = self.visible_conns().len();
+        if len == 0 {
+            self.conn_state.select(None);
+            return;
+        }
+        let current = self.conn_state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, len as isize - 1);
+        self.conn_state.select(Some(next as usize));
+    }
+
     fn on_traffic(&mut self, traffic: Traffic) {
         self.traffic_up.push(traffic.up);
         if self.traffic_up.len() > 300 {
@@ -65,6 +342,9 @@ impl App {
 }
 
 pub async fn run() -> Result<()> {
+    // Check the controller is reachable before taking over the terminal
+    ClashClient::new().get_version().await?;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -119,7 +399,6 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
     });
 
     // Traffic stream
-    let client = ClashClient::new();
     let tx_traffic = tx.clone();
     let client_clone = ClashClient::new();
     tokio::spawn(async move {
@@ -164,26 +443,7 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
     tokio::spawn(async move {
         loop {
             if let Ok(proxies) = client_clone3.get_proxies().await {
-                let mut data = Vec::new();
-                for (name, item) in proxies {
-                    if item.proxy_type == "Selector" || item.proxy_type == "URLTest" {
-                        let now = item.now.unwrap_or_default();
-                        let delay = if let Some(history) = item.history {
-                            history.last().map(|h| h.delay.to_string()).unwrap_or("0".to_string())
-                        } else {
-                            "0".to_string()
-                        };
-                         data.push((name, now, delay));
-                    }
-                }
-                // Sort
-                 data.sort_by(|a, b| {
-                     if a.0 == "GLOBAL" { std::cmp::Ordering::Less }
-                     else if b.0 == "GLOBAL" { std::cmp::Ordering::Greater }
-                     else { a.0.cmp(&b.0) }
-                 });
-
-                if tx_proxies.send(AppEvent::Proxies(data)).await.is_err() {
+                if tx_proxies.send(AppEvent::Proxies(build_groups(&proxies))).await.is_err() {
                     break;
                 }
             }
@@ -191,23 +451,55 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
         }
     });
 
+    // Connections poller (every 1s), computes per-connection speed from the previous snapshot
+    let tx_conns = tx.clone();
+    let client_clone4 = ClashClient::new();
+    tokio::spawn(async move {
+        let mut previous: HashMap<String, (u64, u64)> = HashMap::new();
+        let mut last_poll = Instant::now();
+        loop {
+            if let Ok(snapshot) = client_clone4.get_connections().await {
+                let elapsed = last_poll.elapsed().as_secs_f64().max(0.001);
+                last_poll = Instant::now();
+                let rows: Vec<ConnRow> = snapshot.connections.into_iter()
+                    .map(|conn| {
+                        let (up, down) = previous.get(&conn.id).copied().unwrap_or((conn.upload, conn.download));
+                        ConnRow {
+                            up_speed: (conn.upload.saturating_sub(up) as f64 / elapsed) as u64,
+                            down_speed: (conn.download.saturating_sub(down) as f64 / elapsed) as u64,
+                            conn,
+                        }
+                    })
+                    .collect();
+                previous = rows.iter()
+                    .map(|r| (r.conn.id.clone(), (r.conn.upload, r.conn.download)))
+                    .collect();
+
+                if tx_conns.send(AppEvent::Connections(rows)).await.is_err() {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    });
+
     loop {
-        terminal.draw(|f| ui(f, &app))?;
+        terminal.draw(|f| ui(f, &mut app))?;
 
         match rx.recv().await {
             Some(AppEvent::Input(event)) => {
-                 if let Event::Key(key) = event {
+                if let Event::Key(key) = event {
                     if key.kind == KeyEventKind::Press {
-                        if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
-                            app.should_quit = true;
-                        }
+                        handle_key(&mut app, key, &tx);
                     }
                 }
             }
             Some(AppEvent::Tick) => {}
             Some(AppEvent::Traffic(t)) => app.on_traffic(t),
             Some(AppEvent::Log(l)) => app.on_log(l),
-            Some(AppEvent::Proxies(p)) => app.proxies = p,
+            Some(AppEvent::Proxies(p)) => app.on_proxies(p),
+            Some(AppEvent::Connections(c)) => app.on_connections(c),
+            Some(AppEvent::Status(s)) => app.status = Some(s),
             None => break,
         }
 
@@ -219,14 +511,356 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
     Ok(())
 }
 
-fn ui(f: &mut ratatui::Frame, app: &App) {
+fn handle_key(app: &mut App, key: KeyEvent, tx: &mpsc::Sender<AppEvent>) {
+    // While editing the filter every printable key goes into the filter text
+    if app.filter_editing {
+        let selected = app.selected_conn_id();
+        match key.code {
+            KeyCode::Enter | KeyCode::Esc => app.filter_editing = false,
+            KeyCode::Backspace => { app.conn_filter.pop(); }
+            KeyCode::Char(c) => app.conn_filter.push(c),
+            _ => {}
+        }
+        app.reselect_conn(selected);
+        return;
+    }
+
+    // Closing every connection needs an explicit `y`; any other key cancels
+    if app.confirm_close_all {
+        app.confirm_close_all = false;
+        if key.code == KeyCode::Char('y') {
+            spawn_close_all(tx.clone());
+        } else {
+            app.status = Some("已取消关闭全部连接".to_string());
+        }
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
+        KeyCode::Tab => {
+            app.tab = match app.tab {
+                Tab::Overview => Tab::Connections,
+                Tab::Connections => Tab::Proxies,
+                Tab::Proxies => Tab::Overview,
+            };
+        }
+        KeyCode::BackTab => {
+            app.tab = match app.tab {
+                Tab::Overview => Tab::Proxies,
+                Tab::Connections => Tab::Overview,
+                Tab::Proxies => Tab::Connections,
+            };
+        }
+        KeyCode::Char('1') => app.tab = Tab::Overview,
+        KeyCode::Char('2') => app.tab = Tab::Connections,
+        KeyCode::Char('3') => app.tab = Tab::Proxies,
+        _ if app.tab == Tab::Connections => handle_conn_key(app, key, tx),
+        _ if app.tab == Tab::Proxies => handle_proxy_key(app, key, tx),
+        _ => {}
+    }
+}
+
+fn handle_proxy_key(app: &mut App, key: KeyEvent, tx: &mpsc::Sender<AppEvent>) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => app.move_proxy_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_proxy_selection(1),
+        KeyCode::PageUp => app.move_proxy_selection(-10),
+        KeyCode::PageDown => app.move_proxy_selection(10),
+        KeyCode::Right | KeyCode::Char('l') => app.set_expanded(true),
+        KeyCode::Left | KeyCode::Char('h') => app.set_expanded(false),
+        KeyCode::Enter | KeyCode::Char(' ') => match app.selected_proxy_row() {
+            Some(ProxyRow::Group(gi)) => {
+                let expand = !app.expanded.contains(&app.proxies[gi].name);
+                app.set_expanded(expand);
+            }
+            Some(ProxyRow::Member(gi, mi)) => {
+                let group = &app.proxies[gi];
+                if group.group_type != "Selector" {
+                    app.status = Some(format!("{} 是 {} 类型, 不支持手动选择", group.name, group.group_type));
+                } else {
+                    spawn_select(group.name.clone(), group.members[mi].name.clone(), tx.clone());
+                }
+            }
+            None => {}
+        },
+        KeyCode::Char('t') => {
+            if let Some(ProxyRow::Group(gi) | ProxyRow::Member(gi, _)) = app.selected_proxy_row() {
+                let group = &app.proxies[gi];
+                let members = group.members.iter().map(|m| m.name.clone()).collect();
+                app.status = Some(format!("正在测试 {} 的 {} 个节点...", group.name, group.members.len()));
+                spawn_group_test(group.name.clone(), members, tx.clone());
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Fetch the latest groups and push them to the UI
+async fn refresh_proxies(client: &ClashClient, tx: &mpsc::Sender<AppEvent>) {
+    if let Ok(proxies) = client.get_proxies().await {
+        let _ = tx.send(AppEvent::Proxies(build_groups(&proxies))).await;
+    }
+}
+
+/// Switch the selected node of a group in the background
+fn spawn_select(group: String, node: String, tx: mpsc::Sender<AppEvent>) {
+    tokio::spawn(async move {
+        let client = ClashClient::new();
+        let msg = match client.select_proxy(&group, &node).await {
+            Ok(()) => format!("已将 {} 切换至 {}", group, node),
+            Err(e) => format!("切换失败: {}", e),
+        };
+        let _ = tx.send(AppEvent::Status(msg)).await;
+        refresh_proxies(&client, &tx).await;
+    });
+}
+
+/// Delay-test every member of a group concurrently in the background
+fn spawn_group_test(group: String, members: Vec<String>, tx: mpsc::Sender<AppEvent>) {
+    tokio::spawn(async move {
+        let client = ClashClient::new();
+        let total = members.len();
+        let results = test_delays(&client, &members, &DelayTest::default(), 16).await;
+        let ok = results.iter().filter(|(_, r)| r.is_ok()).count();
+        let _ = tx.send(AppEvent::Status(format!("{} 测速完成: {}/{} 可用", group, ok, total))).await;
+        // The core records the results in each node's history
+        refresh_proxies(&client, &tx).await;
+    });
+}
+
+fn handle_conn_key(app: &mut App, key: KeyEvent, tx: &mpsc::Sender<AppEvent>) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => app.move_conn_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_conn_selection(1),
+        KeyCode::PageUp => app.move_conn_selection(-10),
+        KeyCode::PageDown => app.move_conn_selection(10),
+        KeyCode::Char('/') => app.filter_editing = true,
+        KeyCode::Char('s') => {
+            let selected = app.selected_conn_id();
+            app.conn_sort = app.conn_sort.next();
+            app.reselect_conn(selected);
+        }
+        KeyCode::Char('r') => {
+            let selected = app.selected_conn_id();
+            app.sort_desc = !app.sort_desc;
+            app.reselect_conn(selected);
+        }
+        KeyCode::Char('x') => {
+            if let Some(id) = app.selected_conn_id() {
+                spawn_close(vec![id], tx.clone());
+            }
+        }
+        KeyCode::Char('X') => {
+            let ids: Vec<String> = app.visible_conns().iter().map(|r| r.conn.id.clone()).collect();
+            if app.conn_filter.is_empty() {
+                // No filter means every connection; ask before closing
+                app.confirm_close_all = true;
+            } else if !ids.is_empty() {
+                spawn_close(ids, tx.clone());
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Close every connection with a single request and report the result in the status line
+fn spawn_close_all(tx: mpsc::Sender<AppEvent>) {
+    tokio::spawn(async move {
+        let msg = match ClashClient::new().close_all_connections().await {
+            Ok(()) => "已关闭所有连接".to_string(),
+            Err(e) => format!("关闭连接失败: {}", e),
+        };
+        let _ = tx.send(AppEvent::Status(msg)).await;
+    });
+}
+
+/// Close connections in the background and report the result in the status line
+fn spawn_close(ids: Vec<String>, tx: mpsc::Sender<AppEvent>) {
+    tokio::spawn(async move {
+        let client = ClashClient::new();
+        let mut closed = 0;
+        let mut error = None;
+        for id in &ids {
+            match client.close_connection(id).await {
+                Ok(()) => closed += 1,
+                Err(e) => error = Some(e),
+            }
+        }
+        let msg = match error {
+            Some(e) => format!("已关闭 {} 条连接, 部分失败: {}", closed, e),
+            None => format!("已关闭 {} 条连接", closed),
+        };
+        let _ = tx.send(AppEvent::Status(msg)).await;
+    });
+}
+
+fn ui(f: &mut ratatui::Frame, app: &mut App) {
+    let outer = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .split(f.size());
+
+    let tabs = Tabs::new(vec![
+        " 1 概览 (Overview) ".to_string(),
+        format!(" 2 连接 (Connections: {}) ", app.conns.len()),
+        " 3 节点 (Proxies) ".to_string(),
+    ])
+        .select(match app.tab { Tab::Overview => 0, Tab::Connections => 1, Tab::Proxies => 2 })
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+    f.render_widget(tabs, outer[0]);
+
+    match app.tab {
+        Tab::Overview => draw_overview(f, app, outer[1]),
+        Tab::Connections => draw_connections(f, app, outer[1]),
+        Tab::Proxies => draw_proxies(f, app, outer[1]),
+    }
+}
+
+fn draw_proxies(f: &mut ratatui::Frame, app: &mut App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let items: Vec<ListItem> = app.proxy_rows().into_iter()
+        .map(|row| match row {
+            ProxyRow::Group(gi) => {
+                let group = &app.proxies[gi];
+                let marker = if app.expanded.contains(&group.name) { "▼" } else { "▶" };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} ", marker)),
+                    Span::styled(group.name.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!(" [{}] ", group.group_type), Style::default().fg(Color::DarkGray)),
+                    Span::raw(format!("{} ", group.now)),
+                    Span::styled(format!("({}ms)", group.delay), delay_style(group.delay)),
+                ]))
+            }
+            ProxyRow::Member(gi, mi) => {
+                let group = &app.proxies[gi];
+                let member = &group.members[mi];
+                let current = member.name == group.now;
+                let name_style = if current {
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                let delay = if member.delay == 0 { "--".to_string() } else { format!("{}ms", member.delay) };
+                ListItem::new(Line::from(vec![
+                    Span::raw(if current { "    ● " } else { "      " }),
+                    Span::styled(member.name.clone(), name_style),
+                    Span::raw(" "),
+                    Span::styled(delay, delay_style(member.delay)),
+                ]))
+            }
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("节点 (Proxies)"))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, chunks[0], &mut app.proxy_state);
+
+    let mut spans = vec![Span::styled(
+        "↑/↓ 选择  ←/→ 折叠/展开  Enter 展开或切换节点  t 测速当前组  Tab 切换  q 退出",
+        Style::default().fg(Color::DarkGray),
+    )];
+    if let Some(status) = &app.status {
+        spans.push(Span::styled(format!("  {}", status), Style::default().fg(Color::Green)));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), chunks[1]);
+}
+
+fn draw_connections(f: &mut ratatui::Frame, app: &mut App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let now = Utc::now();
+    let rows: Vec<Row> = app.visible_conns().iter()
+        .map(|r| {
+            Row::new(vec![
+                r.conn.destination(),
+                r.conn.chain_display(),
+                r.conn.rule_display(),
+                format!("{}/s", format_bytes(r.up_speed)),
+                format!("{}/s", format_bytes(r.down_speed)),
+                format_bytes(r.conn.upload),
+                format_bytes(r.conn.download),
+                format_duration((now - r.conn.start).num_seconds()),
+            ])
+        })
+        .collect();
+    let visible = rows.len();
+
+    let header = Row::new(vec!["主机", "代理链", "规则", "↑ 速度", "↓ 速度", "↑ 总计", "↓ 总计", "时长"])
+        .style(Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan));
+    let widths = [
+        Constraint::Min(24),
+        Constraint::Length(24),
+        Constraint::Length(24),
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(7),
+    ];
+    let order = if app.sort_desc { "↓" } else { "↑" };
+    let title = if app.conn_filter.is_empty() {
+        format!("连接 (Connections) {} 条 · 排序: {}{}", visible, app.conn_sort.label(), order)
+    } else {
+        format!("连接 (Connections) {}/{} 条 · 过滤: {} · 排序: {}{}", visible, app.conns.len(), app.conn_filter, app.conn_sort.label(), order)
+    };
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(table, chunks[0], &mut app.conn_state);
+
+    let footer = if app.filter_editing {
+        Line::from(vec![
+            Span::styled("过滤: ", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{}_", app.conn_filter)),
+            Span::styled("  (Enter 确认)", Style::default().fg(Color::DarkGray)),
+        ])
+    } else if app.confirm_close_all {
+        Line::from(Span::styled(
+            format!("确定关闭全部 {} 条连接? 按 y 确认，其他键取消", app.conns.len()),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ))
+    } else {
+        let mut spans = vec![Span::styled(
+            "↑/↓ 选择  / 过滤  s 排序  r 反序  x 关闭选中  X 关闭全部匹配  Tab 切换  q 退出",
+            Style::default().fg(Color::DarkGray),
+        )];
+        if let Some(status) = &app.status {
+            spans.push(Span::styled(format!("  {}", status), Style::default().fg(Color::Green)));
+        }
+        Line::from(spans)
+    };
+    f.render_widget(Paragraph::new(footer), chunks[1]);
+}
+
+fn draw_overview(f: &mut ratatui::Frame, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Percentage(66),
             Constraint::Percentage(34),
         ])
-        .split(f.size());
+        .split(area);
     
     // Top section: Logs (Left 2/3) and Proxies (Right 1/3)
     let top_chunks = Layout::default()
@@ -254,16 +888,11 @@ fn ui(f: &mut ratatui::Frame, app: &App) {
 
     // Proxies
     let proxies: Vec<ListItem> = app.proxies.iter()
-        .map(|(name, now, delay)| {
-            let delay_val = delay.parse::<u64>().unwrap_or(0);
-            let style = if delay_val > 0 && delay_val < 500 { Style::default().fg(Color::Green) } 
-                       else if delay_val == 0 { Style::default().fg(Color::Red) }
-                       else { Style::default().fg(Color::Yellow) };
-            
+        .map(|group| {
             ListItem::new(Line::from(vec![
-                Span::styled(format!("{}: ", name), Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(format!("{} ", now)),
-                Span::styled(format!("({}ms)", delay), style),
+                Span::styled(format!("{}: ", group.name), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(format!("{} ", group.now)),
+                Span::styled(format!("({}ms)", group.delay), delay_style(group.delay)),
             ]))
         })
         .collect();
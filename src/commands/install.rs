@@ -1,12 +1,14 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Result, Context, anyhow};
 use std::process::Command;
 use std::path::{Path};
-use crate::utils::{system, download};
+use crate::clash::release;
+use crate::utils::{archive, system, download};
+use crate::utils::system::ArchInfo;
 use tempfile::TempDir;
 use colored::*;
 use dialoguer::{Confirm, theme::ColorfulTheme};
 
-const MIHOMO_VERSION: &str = "v1.17.0";
+/// 发布列表不可用且指定了完整版本号时, 直接按 GitHub 的下载地址格式拼接
 const DOWNLOAD_BASE_URL: &str = "https://github.com/MetaCubeX/mihomo/releases/download";
 
 // 使用 jsdelivr CDN 加速下载数据库文件
@@ -14,53 +16,79 @@ const MMDB_DOWNLOAD_URL: &str = "https://fastly.jsdelivr.net/gh/MetaCubeX/meta-r
 const GEOSITE_DOWNLOAD_URL: &str = "https://fastly.jsdelivr.net/gh/MetaCubeX/meta-rules-dat@release/geosite.dat";
 const GEOIP_DOWNLOAD_URL: &str = "https://fastly.jsdelivr.net/gh/MetaCubeX/meta-rules-dat@release/geoip.dat";
 
+/// Clash (Mihomo) 内核安装路径
+pub const CLASH_BIN: &str = "/usr/local/bin/clash";
+/// 升级前保留的上一个版本的内核
+pub const CLASH_PREV_BIN: &str = "/usr/local/bin/clash.prev";
+
+const SYSTEMD_DIR: &str = "/etc/systemd/system";
+/// 订阅定时更新的 systemd 单元名称
+const UPDATE_SERVICE: &str = "clash-cli-update.service";
+const UPDATE_TIMER: &str = "clash-cli-update.timer";
+
 /// 安装命令主入口
 /// 
 /// 负责下载、解压、移动二进制文件，并配置 systemd 服务
-pub async fn run(version: Option<String>, file: Option<String>) -> Result<()> {
-    let version = version.as_deref().unwrap_or(MIHOMO_VERSION);
+pub async fn run(version: Option<String>, release_api: &str, file: Option<String>, sha256: Option<String>, arch: Option<String>, dry_run: bool, auto_update: Option<String>) -> Result<()> {
+    let version = version.as_deref().unwrap_or("latest");
     // 检测系统架构和操作系统
-    let arch = system::get_arch()?;
+    let arch = detect_arch(arch.as_deref())?;
     let os = system::get_os()?;
     
     if os != "linux" {
         println!("{}", "警告: 此安装程序仅针对 Linux systemd 进行了优化。".yellow());
     }
 
+    if dry_run {
+        print_system(os, &arch);
+        match &file {
+            Some(local_path) => println!("本地文件: {}", local_path),
+            None => print_target(&resolve_download(release_api, version, os, &arch).await?),
+        }
+        println!("{}", "(--dry-run: 未安装或修改内核，仅可能更新发布列表缓存)".dimmed());
+        return Ok(());
+    }
+
     let temp_dir = TempDir::new()?;
-    let archive_path = if let Some(local_path) = file {
+    // 本地文件无法确定版本, 只检查能否运行
+    let (archive_path, binary_arch, expected_version, checksum) = if let Some(local_path) = file {
         let path = Path::new(&local_path);
         if !path.exists() {
             return Err(anyhow!("指定的本地文件不存在: {}", local_path));
         }
         println!("使用本地文件安装: {}", local_path);
-        // 如果是本地文件，我们直接使用它，或者复制到临时目录
-        // 为了统一逻辑，这里我们可以直接返回 path 的 PathBuf，或者复制
-        // 由于 extract_gzip 接受 Path，我们直接用
-        path.to_path_buf()
+        // 本地文件可以是任意格式的安装包、解压后的目录或可执行文件, 由 extract_binary 识别
+        (path.to_path_buf(), arch.primary().to_string(), None, sha256)
     } else {
-        // 构造下载链接
-        // Example URL: https://github.com/MetaCubeX/mihomo/releases/download/v1.17.0/mihomo-linux-amd64-v1.17.0.gz
-        let filename = format!("mihomo-{}-{}-{}.gz", os, arch, version);
-        let url = format!("{}/{}/{}", DOWNLOAD_BASE_URL, version, filename);
+        let target = resolve_download(release_api, version, os, &arch).await?;
 
-        println!("正在下载 Clash (Mihomo Core {}) 适用于 {}/{}...", version, os, arch);
+        println!("正在下载 Clash (Mihomo Core {}) 适用于 {}/{}...", target.tag, os, target.arch);
         
         // 创建临时目录进行下载
-        let dl_path = temp_dir.path().join(&filename);
+        let dl_path = temp_dir.path().join(&target.filename);
         
-        download::download_file(&url, &dl_path).await?;
-        dl_path
+        download::download_file(&target.url, &dl_path).await?;
+        let checksum = match sha256 {
+            Some(hash) => Some(hash),
+            None => published_checksum(&target, temp_dir.path()).await?,
+        };
+        (dl_path, target.arch, Some(target.version), checksum)
     };
+
+    verify_checksum(&archive_path, checksum.as_deref())?;
     
     println!("正在安装...");
     
     // 解压文件
     let binary_path = temp_dir.path().join("clash");
-    download::extract_gzip(&archive_path, &binary_path)?;
+    archive::extract_binary(&archive_path, &binary_path)?;
+    if os == "linux" {
+        archive::check_elf_arch(&binary_path, &binary_arch)?;
+    }
+    verify_binary(&binary_path, expected_version.as_deref())?;
 
     // 移动到系统目录 /usr/local/bin/clash
-    install_binary(&binary_path, "/usr/local/bin/clash")?;
+    install_binary(&binary_path, CLASH_BIN)?;
     
     // 创建配置目录 /etc/clash
     create_config_dir("/etc/clash")?;
@@ -95,6 +123,9 @@ pub async fn run(version: Option<String>, file: Option<String>) -> Result<()> {
     // 配置 Systemd 服务
     if os == "linux" {
         setup_systemd()?;
+        if let Some(interval) = auto_update {
+            enable_auto_update(&interval)?;
+        }
     } else {
         println!("非 Linux 系统，跳过 systemd 配置。您可以手动运行: clash -d /etc/clash");
     }
@@ -103,6 +134,132 @@ pub async fn run(version: Option<String>, file: Option<String>) -> Result<()> {
     Ok(())
 }
 
+/// 待下载的内核安装包
+pub struct DownloadTarget {
+    pub tag: String,
+    pub filename: String,
+    pub url: String,
+    /// 安装包对应的架构名
+    pub arch: String,
+    /// `clash -v` 应报告的版本
+    pub version: String,
+    /// 发布中的 SHA-256 校验文件地址
+    pub checksum_url: Option<String>,
+}
+
+/// 检测系统架构, `arch` 为 --arch 指定的架构名时不做检测
+pub fn detect_arch(arch: Option<&str>) -> Result<ArchInfo> {
+    match arch {
+        Some(arch) => Ok(ArchInfo::from_override(arch)),
+        None => system::detect_arch(),
+    }
+}
+
+/// 显示检测到的系统信息
+pub fn print_system(os: &str, arch: &ArchInfo) {
+    println!("系统: {}/{} ({})", os, arch.primary(), arch.detail);
+    if arch.candidates.len() > 1 {
+        println!("候选架构: {}", arch.candidates.join(", "));
+    }
+}
+
+/// 显示将要下载的安装包
+pub fn print_target(target: &DownloadTarget) {
+    println!("版本: {}", target.tag);
+    println!("安装包: {}", target.filename);
+    println!("下载地址: {}", target.url);
+    println!("校验文件: {}", target.checksum_url.as_deref().unwrap_or("无"));
+}
+
+/// 解析版本要求, 得到安装包的下载信息
+pub async fn resolve_download(release_api: &str, version: &str, os: &str, arch: &ArchInfo) -> Result<DownloadTarget> {
+    let releases = match release::fetch_releases(release_api).await {
+        Ok(releases) => releases,
+        // 发布列表不可用时, 完整版本号仍可按固定格式下载
+        Err(e) if release::parse_semver(version).is_some_and(|v| v.len() == 3) => {
+            let tag = format!("v{}", version.trim_start_matches('v'));
+            println!("{} {}", "警告: 获取发布列表失败, 直接从 GitHub 下载".yellow(), tag);
+            println!("  原因: {}", e.root_cause());
+            // Example URL: https://github.com/MetaCubeX/mihomo/releases/download/v1.17.0/mihomo-linux-amd64-v1.17.0.gz
+            // 旧版本只提供 amd64/amd64-compatible 等旧架构名, 按优先级逐个检查安装包是否存在
+            for arch in &arch.candidates {
+                let filename = format!("mihomo-{}-{}-{}.gz", os, arch, tag);
+                let url = format!("{}/{}/{}", DOWNLOAD_BASE_URL, tag, filename);
+                if download::url_exists(&url).await {
+                    return Ok(DownloadTarget {
+                        version: tag.clone(), tag, filename, url, arch: arch.clone(), checksum_url: None,
+                    });
+                }
+            }
+            return Err(anyhow!("版本 {} 没有适用于 {}/{} 的安装包，或无法连接 GitHub",
+                tag, os, arch.candidates.join("|")));
+        }
+        Err(e) => return Err(e),
+    };
+    let resolved = release::resolve(&releases, version, os, &arch.candidates)?;
+    Ok(DownloadTarget {
+        tag: resolved.tag,
+        arch: resolved.arch,
+        filename: resolved.asset.name,
+        url: resolved.asset.browser_download_url,
+        version: resolved.version,
+        checksum_url: resolved.checksum.map(|a| a.browser_download_url),
+    })
+}
+
+/// 下载发布中的校验文件并取出安装包的 SHA-256, 发布未提供校验文件时返回 None
+pub async fn published_checksum(target: &DownloadTarget, dir: &Path) -> Result<Option<String>> {
+    let Some(url) = &target.checksum_url else { return Ok(None) };
+    let path = dir.join("checksums");
+    download::download_file(url, &path).await
+        .context("下载校验文件失败，可通过 --sha256 手动指定")?;
+    let content = std::fs::read_to_string(&path).context("读取校验文件失败")?;
+    release::find_checksum(&content, &target.filename)
+        .map(Some)
+        .ok_or_else(|| anyhow!("校验文件中没有 {} 的记录，可通过 --sha256 手动指定", target.filename))
+}
+
+/// 校验安装包的 SHA-256, 不一致时拒绝安装
+pub fn verify_checksum(path: &Path, expected: Option<&str>) -> Result<()> {
+    let Some(expected) = expected else {
+        println!("{}", "警告: 没有可用的 SHA-256 校验值, 跳过完整性校验。".yellow());
+        return Ok(());
+    };
+    if path.is_dir() {
+        return Err(anyhow!("--sha256 只能用于安装包文件，不能用于目录"));
+    }
+    let expected = expected.trim().to_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!("无效的 SHA-256: {}", expected));
+    }
+    let actual = download::sha256_file(path)?;
+    if actual != expected {
+        return Err(anyhow!("安装包 SHA-256 校验失败, 拒绝安装\n  预期: {}\n  实际: {}", expected, actual));
+    }
+    println!("{} {}", "SHA-256 校验通过:".green(), actual);
+    Ok(())
+}
+
+/// 运行 `clash -v` 确认内核可以执行, 并检查报告的版本
+///
+/// 在替换现有内核之前调用, 避免装上无法运行或版本不符的文件
+pub fn verify_binary(path: &Path, expected_version: Option<&str>) -> Result<String> {
+    let output = Command::new(path).arg("-v").output()
+        .context("新内核无法运行 (架构不匹配或文件损坏)")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let reported = stdout.lines().next().unwrap_or_default().trim().to_string();
+    if !output.status.success() || reported.is_empty() {
+        return Err(anyhow!("新内核无法运行: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    if let Some(expected) = expected_version {
+        if !reported.contains(expected) {
+            return Err(anyhow!("新内核报告的版本与预期的 {} 不一致: {}", expected, reported));
+        }
+    }
+    println!("内核版本: {}", reported);
+    Ok(reported)
+}
+
 /// 移动文件到配置目录
 fn move_file_to_config_dir(source: &Path, filename: &str) -> Result<()> {
     let target = format!("/etc/clash/{}", filename);
@@ -120,7 +277,7 @@ fn move_file_to_config_dir(source: &Path, filename: &str) -> Result<()> {
 
 /// 安装二进制文件到目标路径
 /// 如果权限不足会尝试使用 sudo
-fn install_binary(source: &Path, target: &str) -> Result<()> {
+pub fn install_binary(source: &Path, target: &str) -> Result<()> {
     // 尝试直接移动 (如果以 root 运行)
     if std::fs::rename(source, target).is_ok() {
         return Ok(());
@@ -218,19 +375,8 @@ LimitNOFILE=65536
 WantedBy=multi-user.target
 "#;
 
-    let temp_service_path = "/tmp/clash.service";
-    std::fs::write(temp_service_path, service_content)?;
-    
     println!("正在安装 systemd 服务...");
-    let status = Command::new("sudo")
-        .arg("mv")
-        .arg(temp_service_path)
-        .arg("/etc/systemd/system/clash.service")
-        .status()?;
-        
-    if !status.success() {
-        return Err(anyhow!("安装服务文件失败"));
-    }
+    write_unit_file("clash.service", service_content)?;
     
     // 重载 daemon 并启用且立即启动服务
     Command::new("sudo").arg("systemctl").arg("daemon-reload").status()?;
@@ -240,6 +386,129 @@ WantedBy=multi-user.target
     Ok(())
 }
 
+/// 写入 systemd 单元文件 (先写入 /tmp 再通过 sudo 移动)
+fn write_unit_file(name: &str, content: &str) -> Result<()> {
+    let temp_path = format!("/tmp/{}", name);
+    std::fs::write(&temp_path, content)?;
+
+    let status = Command::new("sudo")
+        .arg("mv")
+        .arg(&temp_path)
+        .arg(format!("{}/{}", SYSTEMD_DIR, name))
+        .status()?;
+
+    if !status.success() {
+        return Err(anyhow!("安装服务文件 {} 失败", name));
+    }
+    Ok(())
+}
+
+/// 校验 systemd 时间间隔格式, 如 "30min"、"6h"、"1d 12h"
+fn validate_interval(interval: &str) -> Result<()> {
+    let re = regex::Regex::new(r"^(\d+\s*(s|sec|m|min|h|hr|d|w)?\s*)+$").unwrap();
+    if !re.is_match(interval.trim()) {
+        return Err(anyhow!("无效的更新间隔: {} (示例: 30min、6h、1d)", interval));
+    }
+    Ok(())
+}
+
+/// 启用订阅定时更新
+///
+/// 安装 clash-cli-update.service (执行 `config update --all`) 和对应的 timer。
+/// 下载或校验失败时原配置文件保持不变, 执行结果记录在 journal 中。
+pub fn enable_auto_update(interval: &str) -> Result<()> {
+    validate_interval(interval)?;
+    let exe = std::env::current_exe()
+        .map_err(|e| anyhow!("无法获取 clash-cli 路径: {}", e))?;
+
+    let service_content = format!(r#"[Unit]
+Description=Clash subscription update
+After=network-online.target clash.service
+Wants=network-online.target
+
+[Service]
+Type=oneshot
+Environment=NO_COLOR=1
+ExecStart={} config update --all
+"#, exe.display());
+
+    let timer_content = format!(r#"[Unit]
+Description=Periodic Clash subscription update
+
+[Timer]
+OnBootSec=5min
+OnUnitActiveSec={}
+RandomizedDelaySec=2min
+Unit={}
+
+[Install]
+WantedBy=timers.target
+"#, interval.trim(), UPDATE_SERVICE);
+
+    println!("正在安装订阅定时更新 (间隔 {})...", interval.trim());
+    write_unit_file(UPDATE_SERVICE, &service_content)?;
+    write_unit_file(UPDATE_TIMER, &timer_content)?;
+
+    Command::new("sudo").arg("systemctl").arg("daemon-reload").status()?;
+    let status = Command::new("sudo").arg("systemctl").arg("enable").arg("--now").arg(UPDATE_TIMER).status()?;
+    if !status.success() {
+        return Err(anyhow!("启用 {} 失败", UPDATE_TIMER));
+    }
+
+    println!("{}", "订阅定时更新已启用。".green());
+    Ok(())
+}
+
+/// 停用并删除订阅定时更新
+pub fn disable_auto_update() -> Result<()> {
+    Command::new("sudo").arg("systemctl").arg("disable").arg("--now").arg(UPDATE_TIMER).status().ok();
+    for unit in [UPDATE_TIMER, UPDATE_SERVICE] {
+        Command::new("sudo").arg("rm").arg("-f").arg(format!("{}/{}", SYSTEMD_DIR, unit)).status().ok();
+    }
+    Command::new("sudo").arg("systemctl").arg("daemon-reload").status().ok();
+    println!("{}", "订阅定时更新已停用。".green());
+    Ok(())
+}
+
+/// 显示订阅定时更新状态
+pub fn auto_update_status() -> Result<()> {
+    let timer_path = format!("{}/{}", SYSTEMD_DIR, UPDATE_TIMER);
+    let Ok(timer) = std::fs::read_to_string(&timer_path) else {
+        println!("订阅定时更新: {}", "未启用".yellow());
+        println!("使用 clash-cli config auto-update on 启用。");
+        return Ok(());
+    };
+
+    let enabled = systemctl_query(&["is-enabled", UPDATE_TIMER]).unwrap_or_else(|| "unknown".to_string());
+    let enabled_display = if enabled == "enabled" { enabled.green() } else { enabled.yellow() };
+    println!("订阅定时更新: {}", enabled_display);
+
+    if let Some(interval) = timer.lines().find_map(|l| l.strip_prefix("OnUnitActiveSec=")) {
+        println!("更新间隔: {}", interval.cyan());
+    }
+    let show = |unit: &str, prop: &str| systemctl_query(&["show", unit, "-p", prop, "--value"])
+        .filter(|v| !v.is_empty() && v != "n/a");
+    if let Some(last) = show(UPDATE_TIMER, "LastTriggerUSec") {
+        println!("上次运行: {}", last);
+    }
+    if let Some(next) = show(UPDATE_TIMER, "NextElapseUSecRealtime") {
+        println!("下次运行: {}", next);
+    }
+    match show(UPDATE_SERVICE, "Result").as_deref() {
+        Some("success") => println!("上次结果: {}", "成功".green()),
+        Some(result) => println!("上次结果: {} ({})", "失败".red(), result),
+        None => {}
+    }
+    println!("查看日志: journalctl -u {}", UPDATE_SERVICE);
+    Ok(())
+}
+
+/// 执行 systemctl 查询命令并返回去除空白的输出
+fn systemctl_query(args: &[&str]) -> Option<String> {
+    Command::new("systemctl").args(args).output().ok()
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
 /// 卸载命令
 pub async fn uninstall() -> Result<()> {
     println!("{}...", "正在卸载 Clash".red());
@@ -251,12 +520,16 @@ pub async fn uninstall() -> Result<()> {
     
     // 删除服务文件
     println!("正在删除服务文件...");
-    Command::new("sudo").arg("rm").arg("/etc/systemd/system/clash.service").status().ok();
+    Command::new("sudo").arg("systemctl").arg("disable").arg("--now").arg(UPDATE_TIMER).status().ok();
+    for unit in ["clash.service", UPDATE_SERVICE, UPDATE_TIMER] {
+        Command::new("sudo").arg("rm").arg("-f").arg(format!("{}/{}", SYSTEMD_DIR, unit)).status().ok();
+    }
     Command::new("sudo").arg("systemctl").arg("daemon-reload").status().ok();
     
     // 删除二进制文件
     println!("正在删除二进制文件...");
-    Command::new("sudo").arg("rm").arg("/usr/local/bin/clash").status().ok();
+    Command::new("sudo").arg("rm").arg(CLASH_BIN).status().ok();
+    Command::new("sudo").arg("rm").arg("-f").arg(CLASH_PREV_BIN).status().ok();
     
     // 询问是否删除配置文件
     let confirm = Confirm::with_theme(&ColorfulTheme::default())
@@ -1,4 +1,5 @@
 pub mod install;
+pub mod upgrade;
 pub mod config;
 pub mod node;
 pub mod policy;
@@ -7,3 +8,4 @@ pub mod proxy;
 pub mod check;
 pub mod status;
 pub mod dashboard;
+pub mod conn;
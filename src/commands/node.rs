@@ -1,15 +1,372 @@
-use anyhow::{Result, Context};
-use crate::clash::api::ClashClient;
+use anyhow::{Result, Context, anyhow};
+use crate::cli::{DelayTestArgs, MatchMode, NodeAction};
+use crate::clash::api::{ClashClient, DelayTest, ProxyItem};
+use crate::clash::error::{ApiResult, ClashApiError};
+use crate::utils::format::pad;
 use dialoguer::{Select, theme::ColorfulTheme};
 use colored::*;
+use futures_util::StreamExt;
+use regex::RegexBuilder;
+use serde::Serialize;
+use std::collections::HashMap;
 
 /// 节点管理命令入口
 ///
-/// 负责获取代理组列表，并进行交互式节点选择
-pub async fn run(_select: bool) -> Result<()> {
+/// 指定子命令时非交互执行；否则在终端中 (或指定 --select 时) 进行交互式选择，
+/// 在脚本等非交互环境中则输出节点列表
+pub async fn run(select: bool, action: Option<NodeAction>) -> Result<()> {
+    match action {
+        Some(NodeAction::Set { group, node, mode }) => set_node(&group, &node, mode).await,
+        Some(NodeAction::List { group, json }) => list_nodes(group.as_deref(), json).await,
+        Some(NodeAction::Test { group, test, group_api }) => test_nodes(group.as_deref(), &test, group_api).await,
+        Some(NodeAction::Auto { group, filter, tolerance, watch, margin, interval, test }) => {
+            auto_select(&group, filter.as_deref(), tolerance, watch.then(|| margin.unwrap_or(tolerance)), interval, &test).await
+        }
+        None if select || console::user_attended() => interactive_select().await,
+        None => list_nodes(None, false).await,
+    }
+}
+
+/// 可以包含子节点的代理组类型
+fn is_group(item: &ProxyItem) -> bool {
+    matches!(item.proxy_type.as_str(), "Selector" | "URLTest" | "Fallback" | "LoadBalance")
+}
+
+/// 按名称查找代理组 (精确匹配优先，其次忽略大小写)
+fn find_group<'a>(proxies: &'a HashMap<String, ProxyItem>, name: &str) -> Result<(&'a String, &'a ProxyItem)> {
+    if let Some((key, item)) = proxies.get_key_value(name).filter(|(_, p)| is_group(p)) {
+        return Ok((key, item));
+    }
+    let matched: Vec<_> = proxies.iter()
+        .filter(|(n, p)| is_group(p) && n.eq_ignore_ascii_case(name))
+        .collect();
+    match matched.as_slice() {
+        [(key, item)] => Ok((key, item)),
+        [] => {
+            let mut groups: Vec<&String> = proxies.iter().filter(|(_, p)| is_group(p)).map(|(n, _)| n).collect();
+            groups.sort();
+            Err(anyhow!("未找到代理组 {}。可用的代理组: {}", name, join_names(&groups)))
+        }
+        _ => Err(anyhow!("代理组名称 {} 存在多个大小写不同的匹配，请使用精确名称", name)),
+    }
+}
+
+/// 在候选节点中匹配名称
+///
+/// `Auto` 模式依次尝试精确、忽略大小写、子串匹配，返回第一个有结果的匹配方式的结果
+pub fn match_nodes<'a>(candidates: &'a [String], pattern: &str, mode: MatchMode) -> Result<Vec<&'a String>> {
+    let exact = || candidates.iter().filter(|n| n.as_str() == pattern).collect::<Vec<_>>();
+    let ignore_case = || {
+        let pattern = pattern.to_lowercase();
+        candidates.iter().filter(|n| n.to_lowercase() == pattern).collect::<Vec<_>>()
+    };
+    let substring = || {
+        let pattern = pattern.to_lowercase();
+        candidates.iter().filter(|n| n.to_lowercase().contains(&pattern)).collect::<Vec<_>>()
+    };
+
+    let matched = match mode {
+        MatchMode::Exact => exact(),
+        MatchMode::IgnoreCase => ignore_case(),
+        MatchMode::Substring => substring(),
+        MatchMode::Regex => {
+            let re = RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .with_context(|| format!("无效的正则表达式: {}", pattern))?;
+            candidates.iter().filter(|n| re.is_match(n)).collect()
+        }
+        MatchMode::Auto => [exact(), ignore_case(), substring()]
+            .into_iter()
+            .find(|m| !m.is_empty())
+            .unwrap_or_default(),
+    };
+    Ok(matched)
+}
+
+/// 不参与延迟测试的内置策略
+fn is_testable(item: &ProxyItem) -> bool {
+    !matches!(item.proxy_type.as_str(), "Reject" | "RejectDrop" | "Pass" | "Compatible")
+}
+
+/// 并发测试一组节点的延迟, 结果顺序与输入一致
+pub async fn test_delays(client: &ClashClient, names: &[String], opts: &DelayTest, concurrency: usize) -> Vec<(String, ApiResult<u64>)> {
+    let mut results: Vec<(usize, String, ApiResult<u64>)> = futures_util::stream::iter(names.iter().cloned().enumerate())
+        .map(|(i, name)| test_one(client, i, name, opts))
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_by_key(|(i, _, _)| *i);
+    results.into_iter().map(|(_, name, result)| (name, result)).collect()
+}
+
+async fn test_one(client: &ClashClient, index: usize, name: String, opts: &DelayTest) -> (usize, String, ApiResult<u64>) {
+    let result = client.delay_test(&name, opts).await;
+    (index, name, result)
+}
+
+fn join_names(names: &[&String]) -> String {
+    names.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", ")
+}
+
+/// 非交互式切换节点
+async fn set_node(group_name: &str, pattern: &str, mode: MatchMode) -> Result<()> {
+    let client = ClashClient::new();
+    let proxies = client.get_proxies().await?;
+    let (group_name, group) = find_group(&proxies, group_name)?;
+
+    if group.proxy_type != "Selector" {
+        return Err(anyhow!("代理组 {} 的类型是 {}，只有 Selector 类型支持手动切换", group_name, group.proxy_type));
+    }
+
+    let all_nodes = group.all.as_deref().unwrap_or_default();
+    let matched = match_nodes(all_nodes, pattern, mode)?;
+    let node = match matched.as_slice() {
+        [node] => *node,
+        [] => return Err(anyhow!("代理组 {} 中没有匹配 {} 的节点", group_name, pattern)),
+        _ => return Err(anyhow!("{} 匹配到 {} 个节点，请缩小范围: {}", pattern, matched.len(), join_names(&matched))),
+    };
+
+    if group.now.as_deref() == Some(node.as_str()) {
+        println!("{} 已经是当前选择。", node.green());
+        return Ok(());
+    }
+
+    client.select_proxy(group_name, node).await?;
+    println!("已将 {} 切换至 {}", group_name, node.green());
+    Ok(())
+}
+
+/// 批量测试节点延迟并按延迟排序输出
+async fn test_nodes(group_name: Option<&str>, args: &DelayTestArgs, group_api: bool) -> Result<()> {
+    let client = ClashClient::new();
+    let proxies = client.get_proxies().await?;
+    let opts = args.delay_test();
+
+    // 待测节点 (去重, 保持顺序) 以及当前被选中的节点
+    let (names, selected): (Vec<String>, Vec<String>) = match group_name {
+        Some(name) => {
+            let (_, group) = find_group(&proxies, name)?;
+            (group.all.clone().unwrap_or_default(), group.now.iter().cloned().collect())
+        }
+        None => {
+            let mut groups: Vec<(&String, &ProxyItem)> = proxies.iter().filter(|(_, p)| is_group(p)).collect();
+            groups.sort_by_key(|(name, _)| *name);
+            let mut names: Vec<String> = Vec::new();
+            for member in groups.iter().flat_map(|(_, g)| g.all.iter().flatten()) {
+                if !names.contains(member) {
+                    names.push(member.clone());
+                }
+            }
+            (names, groups.iter().filter_map(|(_, g)| g.now.clone()).collect())
+        }
+    };
+    let names: Vec<String> = names.into_iter()
+        .filter(|n| proxies.get(n).map(is_testable).unwrap_or(true))
+        .collect();
+
+    if names.is_empty() {
+        println!("没有可测试的节点。");
+        return Ok(());
+    }
+
+    println!("正在测试 {} 个节点 (并发 {}, 超时 {} ms, {})...", names.len(), args.concurrency, opts.timeout_ms, opts.url);
+
+    let mut results: Vec<(String, ApiResult<u64>)> = match (group_name, group_api) {
+        (Some(name), true) => {
+            let (group_name, _) = find_group(&proxies, name)?;
+            let delays = client.group_delay_test(group_name, &opts).await?;
+            // 组测速接口只返回成功的节点, 无法得知其余节点是超时还是出错
+            names.into_iter()
+                .map(|n| {
+                    let result = match delays.get(&n) {
+                        Some(&d) if d > 0 => Ok(d),
+                        _ => Err(ClashApiError::NoResult(n.clone())),
+                    };
+                    (n, result)
+                })
+                .collect()
+        }
+        _ => test_delays(&client, &names, &opts, args.concurrency).await,
+    };
+
+    // 成功的按延迟升序，失败的排在最后
+    results.sort_by_key(|(name, result)| match result {
+        Ok(delay) => (0, *delay, name.clone()),
+        Err(_) => (1, 0, name.clone()),
+    });
+
+    let width = results.iter().map(|(n, _)| console::measure_text_width(n)).max().unwrap_or(0).max(4);
+    println!("   {}  {}  延迟", pad("节点", width), pad("类型", 12));
+    let mut failed = 0;
+    for (name, result) in &results {
+        let mark = if selected.contains(name) { "*" } else { " " };
+        let proxy_type = proxies.get(name).map(|p| p.proxy_type.as_str()).unwrap_or("");
+        let delay = match result {
+            Ok(d) if *d < 200 => format!("{} ms", d).green(),
+            Ok(d) if *d < 500 => format!("{} ms", d).yellow(),
+            Ok(d) => format!("{} ms", d).red(),
+            Err(e) => {
+                failed += 1;
+                format!("✗ 失败 ({})", e.short()).red().bold()
+            }
+        };
+        println!(" {} {}  {}  {}", mark, pad(name, width), pad(proxy_type, 12), delay);
+    }
+    println!("\n{} 个可用, {} 个失败 (* 为当前选中节点)", (results.len() - failed).to_string().green(), failed.to_string().red());
+
+    Ok(())
+}
+
+/// 自动选择最快节点
+///
+/// `watch` 为 `--watch` 模式下后续评估使用的切换幅度; 首次评估使用 `tolerance`,
+/// 之后定期按切换幅度重新评估，单次评估失败只打印警告不退出
+async fn auto_select(group_name: &str, filter: Option<&str>, tolerance: u64, watch: Option<u64>, interval: u64, args: &DelayTestArgs) -> Result<()> {
+    let client = ClashClient::new();
+    let filter = filter
+        .map(|f| RegexBuilder::new(f).case_insensitive(true).build())
+        .transpose()
+        .context("无效的节点过滤正则表达式")?;
+
+    let Some(margin) = watch else {
+        return evaluate_once(&client, group_name, filter.as_ref(), tolerance, args).await;
+    };
+
+    println!("持续监控代理组 {}，每 {} 秒评估一次 (容差 {} ms, 切换幅度 {} ms)。按 Ctrl+C 退出。",
+        group_name.cyan(), interval, tolerance, margin);
+    let mut threshold = tolerance;
+    loop {
+        print!("[{}] ", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+        match evaluate_once(&client, group_name, filter.as_ref(), threshold, args).await {
+            Ok(()) => threshold = margin,
+            Err(e) => println!("{} {:#}", "评估失败:".red(), e),
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(interval.max(1))).await;
+    }
+}
+
+/// 测试候选节点并在需要时切换
+///
+/// 当前节点失败、不在候选范围内，或比最快节点慢超过容差时才会切换
+async fn evaluate_once(client: &ClashClient, group_name: &str, filter: Option<&regex::Regex>, tolerance: u64, args: &DelayTestArgs) -> Result<()> {
+    let proxies = client.get_proxies().await?;
+    let (group_name, group) = find_group(&proxies, group_name)?;
+    if group.proxy_type != "Selector" {
+        return Err(anyhow!("代理组 {} 的类型是 {}，只有 Selector 类型支持自动切换", group_name, group.proxy_type));
+    }
+
+    let candidates: Vec<String> = group.all.iter().flatten()
+        .filter(|n| filter.map(|re| re.is_match(n)).unwrap_or(true))
+        .filter(|n| proxies.get(*n).map(is_testable).unwrap_or(true))
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        return Err(anyhow!("代理组 {} 中没有符合过滤条件的节点", group_name));
+    }
+
+    let results = test_delays(client, &candidates, &args.delay_test(), args.concurrency).await;
+    let healthy = results.iter().filter(|(_, r)| r.is_ok()).count();
+    let (best, best_delay) = results.iter()
+        .filter_map(|(name, r)| r.as_ref().ok().map(|d| (name, *d)))
+        .min_by_key(|(_, d)| *d)
+        .ok_or_else(|| anyhow!("代理组 {} 的 {} 个候选节点全部测试失败", group_name, candidates.len()))?;
+
+    let current = group.now.as_deref().unwrap_or("");
+    let current_delay = results.iter()
+        .find(|(name, _)| name == current)
+        .and_then(|(_, r)| r.as_ref().ok().copied());
+
+    match current_delay {
+        Some(delay) if delay <= best_delay + tolerance => {
+            println!("保持当前节点 {} ({} ms)，最快为 {} ({} ms)，{}/{} 个节点可用。",
+                current.green(), delay, best, best_delay, healthy, candidates.len());
+            Ok(())
+        }
+        _ => {
+            let reason = match current_delay {
+                Some(delay) => format!("当前节点 {} ms", delay),
+                None if candidates.iter().any(|c| c == current) => "当前节点不可用".to_string(),
+                None => "当前节点不在候选范围内".to_string(),
+            };
+            client.select_proxy(group_name, best).await?;
+            println!("已将 {} 从 {} 切换至 {} ({} ms, {})。",
+                group_name, current, best.green(), best_delay, reason);
+            Ok(())
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GroupListing<'a> {
+    name: &'a str,
+    #[serde(rename = "type")]
+    group_type: &'a str,
+    now: Option<&'a str>,
+    members: Vec<MemberListing<'a>>,
+}
+
+#[derive(Serialize)]
+struct MemberListing<'a> {
+    name: &'a str,
+    selected: bool,
+    /// 最近一次延迟 (ms)，0 表示无数据或测试失败
+    delay: u64,
+    /// 最近一次测试时间
+    tested_at: Option<&'a str>,
+}
+
+/// 列出代理组成员
+///
+/// 默认输出制表符分隔的行: 代理组, 节点, 是否选中 (1/0), 最近延迟 (ms, 0 表示无数据)
+async fn list_nodes(group_name: Option<&str>, json: bool) -> Result<()> {
+    let client = ClashClient::new();
+    let proxies = client.get_proxies().await?;
+
+    let mut groups: Vec<(&String, &ProxyItem)> = match group_name {
+        Some(name) => vec![find_group(&proxies, name)?],
+        None => proxies.iter().filter(|(_, p)| is_group(p)).collect(),
+    };
+    groups.sort_by_key(|(name, _)| *name);
+
+    let listings: Vec<GroupListing> = groups.iter()
+        .map(|(name, group)| GroupListing {
+            name,
+            group_type: &group.proxy_type,
+            now: group.now.as_deref(),
+            members: group.all.iter().flatten()
+                .map(|member| {
+                    let last = proxies.get(member)
+                        .and_then(|p| p.history.as_ref())
+                        .and_then(|h| h.last());
+                    MemberListing {
+                        name: member,
+                        selected: group.now.as_deref() == Some(member.as_str()),
+                        delay: last.map(|h| h.delay).unwrap_or(0),
+                        tested_at: last.map(|h| h.time.as_str()),
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&listings)?);
+    } else {
+        for group in &listings {
+            for member in &group.members {
+                println!("{}\t{}\t{}\t{}", group.name, member.name, member.selected as u8, member.delay);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 交互式选择代理组和节点
+async fn interactive_select() -> Result<()> {
     let client = ClashClient::new();
     // 获取所有代理信息
-    let proxies = client.get_proxies().await.context("连接 Clash API 失败。Clash 是否正在运行？")?;
+    let proxies = client.get_proxies().await?;
 
     // 筛选出类型为 "Selector" 的代理组
     let mut groups: Vec<&String> = proxies.iter()
@@ -73,3 +430,32 @@ pub async fn run(_select: bool) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_nodes() {
+        let nodes: Vec<String> = ["HK 01", "hk 01", "HK 02", "US 01", "JP-Tokyo"].iter().map(|s| s.to_string()).collect();
+        let cases: [(&str, MatchMode, &[&str]); 11] = [
+            // Auto 优先精确匹配, 其次忽略大小写, 最后子串
+            ("HK 01", MatchMode::Auto, &["HK 01"]),
+            ("Hk 01", MatchMode::Auto, &["HK 01", "hk 01"]),
+            ("tokyo", MatchMode::Auto, &["JP-Tokyo"]),
+            ("hk", MatchMode::Auto, &["HK 01", "hk 01", "HK 02"]),
+            ("SG", MatchMode::Auto, &[]),
+            ("hk 02", MatchMode::Exact, &[]),
+            ("hk 02", MatchMode::IgnoreCase, &["HK 02"]),
+            ("01", MatchMode::Substring, &["HK 01", "hk 01", "US 01"]),
+            ("HK 01", MatchMode::Substring, &["HK 01", "hk 01"]),
+            (r"^(hk|us) 01$", MatchMode::Regex, &["HK 01", "hk 01", "US 01"]),
+            ("jp-", MatchMode::Regex, &["JP-Tokyo"]),
+        ];
+        for (pattern, mode, expected) in cases {
+            let matched = match_nodes(&nodes, pattern, mode).unwrap();
+            assert_eq!(matched, expected, "{} {:?}", pattern, mode);
+        }
+        assert!(match_nodes(&nodes, "(hk", MatchMode::Regex).is_err());
+    }
+}
@@ -1,6 +1,19 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use crate::cli::ProxyAction;
+use crate::clash::api::ClashClient;
+use crate::clash::controller::ACTIVE_CONFIG_FILE;
+use colored::*;
 use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// 新配置的生效方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyMethod {
+    /// 通过控制器热重载, 现有连接不受影响
+    Reload,
+    /// 重启 Clash 服务
+    Restart,
+}
 
 /// 服务控制命令入口
 pub async fn run(action: ProxyAction) -> Result<()> {
@@ -37,6 +50,56 @@ fn service_control(action: &str) -> Result<()> {
     Ok(())
 }
 
+/// 使已写入的 config.yaml 生效
+///
+/// 优先通过控制器热重载, 热重载失败或 `restart_reason` 不为空时重启服务。
+/// `client` 应在写入新配置之前创建, 以便连接到正在运行的实例。
+pub async fn reload_or_restart(client: &ClashClient, restart_reason: Option<&str>) -> Result<ApplyMethod> {
+    if let Some(reason) = restart_reason {
+        println!("{}，需要重启服务。", reason);
+    } else {
+        match client.reload_config(ACTIVE_CONFIG_FILE).await {
+            Ok(()) => {
+                println!("{}", "已通过控制器热重载配置，现有连接未中断。".green());
+                return Ok(ApplyMethod::Reload);
+            }
+            Err(e) => println!("{} {}，改为重启服务。", "热重载失败:".yellow(), e),
+        }
+    }
+
+    println!("正在重启 Clash 服务...");
+    let status = Command::new("sudo").arg("systemctl").arg("restart").arg("clash").status()?;
+    if !status.success() {
+        return Err(anyhow!("重启 Clash 服务失败"));
+    }
+    println!("{}", "已重启 Clash 服务。".green());
+    Ok(ApplyMethod::Restart)
+}
+
+/// 等待 Clash 恢复正常: 控制器可以访问且能返回代理列表
+///
+/// 每次都重新解析控制器地址, 以便使用新配置中的 external-controller
+pub async fn wait_healthy(timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let client = ClashClient::new();
+        let check = async {
+            client.get_version().await?;
+            client.get_proxies().await?;
+            Ok::<_, crate::clash::error::ClashApiError>(())
+        };
+        let error = match tokio::time::timeout(Duration::from_secs(3), check).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "请求超时".to_string(),
+        };
+        if Instant::now() >= deadline {
+            return Err(anyhow!("Clash 在 {} 秒内未恢复正常: {}", timeout.as_secs(), error));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
 /// 查看服务状态
 fn service_status() -> Result<()> {
     let _ = Command::new("systemctl")
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use colored::*;
 use crate::clash::api::ClashClient;
+use crate::clash::profile::{ActiveState, ProfileStore};
 use std::process::Command;
 
 /// 显示 Clash 状态
@@ -9,6 +10,7 @@ pub async fn run() -> Result<()> {
 
     // 1. 检查 Systemd 服务状态
     check_service_status();
+    print_active_profile();
 
     // 2. 尝试连接 API 获取实时信息
     let client = ClashClient::new();
@@ -27,8 +29,10 @@ pub async fn run() -> Result<()> {
             // 显示策略模式 (Global/Rule/Direct)
             println!("- {}: {}", "策略模式".bold(), config.mode.green());
         },
-        Err(_) => {
-            println!("- {}: {}", "API 连接".bold(), "失败 (Clash 可能未运行)".red());
+        Err(e) => {
+            // 其余信息都依赖 API，直接返回错误以输出处理建议和退出码
+            println!("- {}: {}", "API 连接".bold(), format!("失败 ({})", e.short()).red());
+            return Err(e.into());
         }
     }
 
@@ -117,6 +121,25 @@ fn check_service_status() {
     }
 }
 
+/// 显示当前使用的配置, 以及 config.yaml 是否被手动修改
+fn print_active_profile() {
+    let state = ProfileStore::load().map(|s| s.active_state()).unwrap_or(ActiveState::Unknown);
+    let (active, drifted) = match state {
+        ActiveState::Clean(a) => (a, false),
+        ActiveState::Drifted(a) => (a, true),
+        ActiveState::Unknown => {
+            println!("- {}: {}", "当前配置".bold(), "未知".dimmed());
+            return;
+        }
+    };
+    let name = active.name.unwrap_or_else(|| "未关联配置".to_string());
+    if drifted {
+        println!("- {}: {} {}", "当前配置".bold(), name.cyan(), "(config.yaml 已被手动修改)".yellow());
+    } else {
+        println!("- {}: {}", "当前配置".bold(), name.cyan());
+    }
+}
+
 fn format_speed(speed: u64) -> String {
     if speed < 1024 {
         format!("{} B/s", speed)
@@ -3,8 +3,10 @@ use crate::cli::InboundMode;
 use std::fs;
 use std::process::Command;
 use colored::*;
-
-const CONFIG_FILE: &str = "/etc/clash/config.yaml";
+use crate::clash::controller::ACTIVE_CONFIG_FILE as CONFIG_FILE;
+use crate::clash::profile::{ActiveState, ProfileStore};
+use crate::commands::config;
+use std::path::Path;
 
 /// 入站模式管理命令入口
 pub async fn run(mode: InboundMode) -> Result<()> {
@@ -13,7 +15,7 @@ pub async fn run(mode: InboundMode) -> Result<()> {
 
 /// 设置入站模式 (Tun, Http Proxy)
 /// 
-/// 通过修改配置文件并重启服务实现
+/// 通过修改配置文件并热重载实现, 热重载失败时重启服务, 仍失败则回滚
 async fn set_inbound(mode: InboundMode) -> Result<()> {
     // 检查配置文件是否存在
     if fs::metadata(CONFIG_FILE).is_err() {
@@ -31,6 +33,7 @@ async fn set_inbound(mode: InboundMode) -> Result<()> {
     };
 
     let mut doc: serde_yaml::Value = serde_yaml::from_str(&content).context("解析配置文件失败")?;
+    let original = doc.clone();
     
     // 检查当前状态，避免不必要的重启
     let is_tun_enabled = doc.get("tun")
@@ -81,27 +84,37 @@ async fn set_inbound(mode: InboundMode) -> Result<()> {
         },
     }
     
-    // 写回配置文件
+    // 写回配置文件 (备份原配置, 生效失败时自动回滚)
     let new_content = serde_yaml::to_string(&doc)?;
     let temp_path = "/tmp/clash_config_update.yaml";
     fs::write(temp_path, new_content)?;
-    
-    let status = Command::new("sudo")
-        .arg("cp")
-        .arg(temp_path)
-        .arg(CONFIG_FILE)
-        .status()?;
-        
-    if !status.success() {
-        return Err(anyhow::anyhow!("更新配置文件失败"));
+    // 切换前已被手动修改的配置保持"已修改"状态, 不因重新计算哈希而被掩盖
+    let mut store = ProfileStore::load()?;
+    let was_clean = matches!(store.active_state(), ActiveState::Clean(_));
+    config::switch_active_config(Path::new(temp_path), restart_reason(&original, &doc)).await?;
+    if was_clean {
+        config::record_active(&mut store, |store, content| store.touch_active(content));
     }
     
-    println!("正在重启 Clash...");
-    Command::new("sudo").arg("systemctl").arg("restart").arg("clash").status()?;
-    
     Ok(())
 }
 
+/// 判断新旧配置之间的 TUN 变化是否需要重启服务
+///
+/// 开关 TUN 可以通过热重载完成; TUN 保持开启但设备参数 (stack、device、路由等) 改变时,
+/// 热重载无法可靠地重建网卡, 需要重启
+pub fn restart_reason(old: &serde_yaml::Value, new: &serde_yaml::Value) -> Option<&'static str> {
+    let enabled = |doc: &serde_yaml::Value| doc.get("tun")
+        .and_then(|v| v.get("enable"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if enabled(old) && enabled(new) && old.get("tun") != new.get("tun") {
+        Some("TUN 设备参数已变化")
+    } else {
+        None
+    }
+}
+
 fn disable_tun(doc: &mut serde_yaml::Value) {
     if let Some(tun) = doc.get_mut("tun") {
         if let Some(enable) = tun.get_mut("enable") {
@@ -109,3 +122,29 @@ fn disable_tun(doc: &mut serde_yaml::Value) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_restart_reason() {
+        let cases = [
+            // 开关 TUN 可以热重载
+            ("tun: {enable: false}", "tun: {enable: true, stack: system}", false),
+            ("tun: {enable: true, stack: system}", "tun: {enable: false, stack: system}", false),
+            ("mixed-port: 7890", "tun: {enable: true}", false),
+            // 保持开启且参数不变
+            ("tun: {enable: true, stack: system}\nmode: rule", "tun: {enable: true, stack: system}\nmode: global", false),
+            // 保持开启但设备参数变化
+            ("tun: {enable: true, stack: system}", "tun: {enable: true, stack: gvisor}", true),
+            ("tun: {enable: true}", "tun: {enable: true, auto-route: true}", true),
+            ("tun: {enable: false, stack: system}", "tun: {enable: false, stack: gvisor}", false),
+        ];
+        for (old, new, restart) in cases {
+            let old: serde_yaml::Value = serde_yaml::from_str(old).unwrap();
+            let new: serde_yaml::Value = serde_yaml::from_str(new).unwrap();
+            assert_eq!(restart_reason(&old, &new).is_some(), restart, "{:?} => {:?}", old, new);
+        }
+    }
+}
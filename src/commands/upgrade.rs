@@ -0,0 +1,152 @@
+use anyhow::{Result, anyhow};
+use crate::clash::api::ClashClient;
+use crate::clash::release;
+use crate::commands::{install, proxy};
+use crate::commands::install::{CLASH_BIN, CLASH_PREV_BIN};
+use crate::utils::{archive, download, system};
+use colored::*;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// 新内核启动后等待控制器响应的时间
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 升级命令主入口
+///
+/// 只替换内核二进制文件, 不改动 systemd 服务、配置和数据库文件
+pub async fn run(version: Option<String>, release_api: &str, arch: Option<String>, rollback: bool, check: bool) -> Result<()> {
+    if !Path::new(CLASH_BIN).exists() {
+        return Err(anyhow!("未找到 {}，请先运行 clash-cli install", CLASH_BIN));
+    }
+    if rollback {
+        return rollback_binary().await;
+    }
+
+    let spec = version.as_deref().unwrap_or("latest");
+    let arch = install::detect_arch(arch.as_deref())?;
+    let os = system::get_os()?;
+    let target = install::resolve_download(release_api, spec, os, &arch).await?;
+    let current = current_version();
+
+    println!("当前版本: {}", current.as_deref().unwrap_or("未知"));
+    println!("目标版本: {}", target.version);
+    if current.as_deref() == Some(target.version.as_str()) {
+        println!("{}", "已是目标版本，无需升级。".green());
+        return Ok(());
+    }
+    if check {
+        install::print_system(os, &arch);
+        println!("安装包: {}", target.filename);
+        let command = format!("clash-cli upgrade{}", version.map(|v| format!(" {}", v)).unwrap_or_default());
+        // 预发布版 (alpha-<commit>) 或无法获取当前版本时无法比较新旧
+        match (current.as_deref().and_then(release::parse_semver), release::parse_semver(&target.version)) {
+            (Some(cur), Some(new)) if new > cur => println!("{}运行 {} 升级。", "发现新版本。".green(), command),
+            (Some(_), Some(_)) => println!("当前版本比 {} 更新。", target.version),
+            _ => println!("{}如需安装 {}，请运行 {}。", "无法比较版本新旧。".yellow(), target.version, command),
+        }
+        return Ok(());
+    }
+
+    let temp_dir = TempDir::new()?;
+    println!("正在下载 Clash (Mihomo Core {}) 适用于 {}/{}...", target.tag, os, target.arch);
+    let archive_path = temp_dir.path().join(&target.filename);
+    download::download_file(&target.url, &archive_path).await?;
+    let checksum = install::published_checksum(&target, temp_dir.path()).await?;
+    install::verify_checksum(&archive_path, checksum.as_deref())?;
+
+    let binary_path = temp_dir.path().join("clash");
+    archive::extract_binary(&archive_path, &binary_path)?;
+    if os == "linux" {
+        archive::check_elf_arch(&binary_path, &target.arch)?;
+    }
+    install::verify_binary(&binary_path, Some(&target.version))?;
+
+    // 保留当前内核, 新内核无法启动时恢复
+    sudo(&["cp", "-p", CLASH_BIN, CLASH_PREV_BIN], "保留当前内核失败")?;
+    install::install_binary(&binary_path, CLASH_BIN)?;
+    println!("已保留之前的内核: {}", CLASH_PREV_BIN);
+
+    let Err(e) = restart_and_check(&target.version).await else {
+        println!("{}", format!("已升级到 {}。", target.version).green().bold());
+        return Ok(());
+    };
+    println!("{} {:#}", "新内核未能正常运行:".red(), e);
+    println!("正在恢复之前的内核...");
+    replace_binary(CLASH_PREV_BIN)?;
+    match restart_and_check("").await {
+        Ok(_) => Err(anyhow!("升级失败，已恢复之前的内核: {:#}", e)),
+        Err(restore_err) => Err(anyhow!("升级失败，且恢复之前的内核后 Clash 仍未正常运行: {:#}", restore_err)),
+    }
+}
+
+/// 与升级前保留的内核互换, 再次执行可撤销
+async fn rollback_binary() -> Result<()> {
+    if !Path::new(CLASH_PREV_BIN).exists() {
+        return Err(anyhow!("没有可恢复的内核 ({} 不存在)", CLASH_PREV_BIN));
+    }
+    let staging = format!("{}.swap", CLASH_BIN);
+    sudo(&["cp", "-p", CLASH_BIN, &staging], "保留当前内核失败")?;
+    replace_binary(CLASH_PREV_BIN)?;
+    sudo(&["mv", "-f", &staging, CLASH_PREV_BIN], "保留当前内核失败")?;
+
+    let version = current_version().unwrap_or_else(|| "未知".to_string());
+    println!("已恢复内核 {}，当前内核保存为 {}", version, CLASH_PREV_BIN);
+    restart_and_check(&version).await?;
+    println!("{}", "回滚完成。".green().bold());
+    Ok(())
+}
+
+/// 用指定文件替换内核
+///
+/// 先复制到同目录再重命名, 避免覆盖正在运行的可执行文件时出现 "Text file busy"
+fn replace_binary(source: &str) -> Result<()> {
+    let staging = format!("{}.new", CLASH_BIN);
+    sudo(&["cp", "-p", source, &staging], "复制内核失败")?;
+    sudo(&["mv", "-f", &staging, CLASH_BIN], "替换内核失败")
+}
+
+/// 重启服务并等待控制器返回版本号, `expected` 非空时检查版本是否一致
+async fn restart_and_check(expected: &str) -> Result<()> {
+    proxy::reload_or_restart(&ClashClient::new(), Some("内核已更换")).await?;
+    let client = ClashClient::new();
+    let deadline = Instant::now() + HEALTH_TIMEOUT;
+    loop {
+        let error = match tokio::time::timeout(Duration::from_secs(3), client.get_version()).await {
+            Ok(Ok(v)) if v.version.contains(expected) => {
+                println!("运行中的内核版本: {}", v.version);
+                return Ok(());
+            }
+            Ok(Ok(v)) => format!("运行中的内核版本为 {}，与预期的 {} 不一致", v.version, expected),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "请求超时".to_string(),
+        };
+        if Instant::now() >= deadline {
+            return Err(anyhow!("Clash 在 {} 秒内未恢复正常: {}", HEALTH_TIMEOUT.as_secs(), error));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// 已安装内核的版本号 (v1.18.1 或 alpha-<commit>), 取自 `clash -v` 的输出
+fn current_version() -> Option<String> {
+    let output = Command::new(CLASH_BIN).arg("-v").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first_line = stdout.lines().next()?;
+    first_line.split_whitespace()
+        .find(|token| {
+            (token.starts_with('v') && release::parse_semver(token).is_some())
+                || token.starts_with("alpha-")
+                || token.starts_with("beta-")
+        })
+        .map(|token| token.to_string())
+}
+
+fn sudo(args: &[&str], error: &str) -> Result<()> {
+    let status = Command::new("sudo").args(args).status()?;
+    if !status.success() {
+        return Err(anyhow!("{}", error));
+    }
+    Ok(())
+}
@@ -5,17 +5,32 @@ mod clash;
 
 use clap::Parser;
 use cli::{Cli, Commands};
+use clash::error::ClashApiError;
+use colored::*;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
     // 解析命令行参数
     let cli = Cli::parse();
 
-    // 根据子命令执行对应的功能模块
-    match cli.command {
-        Commands::Install { version, file } => {
+    // 设置 API 控制器地址与密钥覆盖参数
+    clash::controller::init(cli.api_url, cli.api_socket, cli.secret);
+
+    if let Err(e) = run(cli.command).await {
+        std::process::exit(report_error(&e));
+    }
+}
+
+/// 根据子命令执行对应的功能模块
+async fn run(command: Commands) -> anyhow::Result<()> {
+    match command {
+        Commands::Install { version, release_api, file, sha256, arch, dry_run, auto_update } => {
             // 安装命令
-            commands::install::run(version, file).await?;
+            commands::install::run(version, &release_api, file, sha256, arch, dry_run, auto_update).await?;
+        }
+        Commands::Upgrade { version, release_api, arch, rollback, check } => {
+            // 内核升级命令
+            commands::upgrade::run(version, &release_api, arch, rollback, check).await?;
         }
         Commands::Uninstall => {
             // 卸载命令
@@ -25,9 +40,9 @@ async fn main() -> anyhow::Result<()> {
             // 配置管理命令
             commands::config::run(action).await?;
         }
-        Commands::Node { select } => {
+        Commands::Node { select, action } => {
             // 节点管理命令
-            commands::node::run(select).await?;
+            commands::node::run(select, action).await?;
         }
         Commands::Policy { mode } => {
             // 路由策略切换命令
@@ -45,6 +60,10 @@ async fn main() -> anyhow::Result<()> {
             // 服务控制命令
             commands::proxy::run(action).await?;
         }
+        Commands::Conn { action } => {
+            // 连接管理命令
+            commands::conn::run(action).await?;
+        }
         Commands::Status => {
             // 状态查看命令
             commands::status::run().await?;
@@ -57,3 +76,17 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// 打印错误信息并返回进程退出码
+///
+/// Clash API 错误会附带处理建议和独立的退出码, 其他错误返回 1
+fn report_error(err: &anyhow::Error) -> i32 {
+    if let Some(api_err) = err.chain().find_map(|e| e.downcast_ref::<ClashApiError>()) {
+        eprintln!("{} {:#}", "错误:".red().bold(), err);
+        eprintln!("{} {}", "提示:".yellow(), api_err.hint());
+        api_err.exit_code()
+    } else {
+        eprintln!("{} {:?}", "错误:".red().bold(), err);
+        1
+    }
+}
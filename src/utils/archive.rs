@@ -0,0 +1,312 @@
+use std::fs::{self, File};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use anyhow::{Result, Context, anyhow};
+use flate2::read::GzDecoder;
+
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+
+/// 从安装包中取出内核可执行文件
+///
+/// 根据文件头识别格式, 支持 .gz、.tar.gz、.tar、.zip、.deb、未压缩的可执行文件
+/// 以及解压后的目录 (如 .deb 解包得到的 usr/bin/mihomo)。
+/// 输出文件会被赋予 755 权限 (Unix)
+pub fn extract_binary(source: &Path, output: &Path) -> Result<()> {
+    let binary = if source.is_dir() {
+        find_in_dir(source)?
+    } else {
+        let data = fs::read(source).context("Failed to open archive")?;
+        from_bytes(data, &source.display().to_string())?
+    };
+    fs::write(output, binary).context("Failed to create output file")?;
+
+    // chmod +x
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(output, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// 按文件头识别格式并取出可执行文件
+fn from_bytes(data: Vec<u8>, name: &str) -> Result<Vec<u8>> {
+    if data.starts_with(ELF_MAGIC) {
+        return Ok(data);
+    }
+    if data.starts_with(GZIP_MAGIC) {
+        let mut decoded = Vec::new();
+        GzDecoder::new(data.as_slice()).read_to_end(&mut decoded).context("Failed to extract content")?;
+        // .tar.gz 解压后是 tar 包, 单文件 .gz 解压后就是可执行文件
+        return if is_tar(&decoded) { from_tar(&decoded) } else { Ok(decoded) };
+    }
+    if data.starts_with(ZIP_MAGIC) {
+        return from_zip(data);
+    }
+    if data.starts_with(AR_MAGIC) {
+        return from_deb(&data);
+    }
+    if is_tar(&data) {
+        return from_tar(&data);
+    }
+    Err(anyhow!("无法识别 {} 的格式 (支持 .gz、.tar.gz、.zip、.deb 和可执行文件)", name))
+}
+
+/// tar 包在偏移 257 处有 "ustar" 标记
+fn is_tar(data: &[u8]) -> bool {
+    data.get(257..262) == Some(b"ustar")
+}
+
+fn from_tar(data: &[u8]) -> Result<Vec<u8>> {
+    let mut archive = tar::Archive::new(data);
+    let mut candidates = Vec::new();
+    for entry in archive.entries().context("tar 包格式错误")? {
+        let mut entry = entry.context("tar 包格式错误")?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().to_string();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        if content.starts_with(ELF_MAGIC) {
+            candidates.push((path, content));
+        }
+    }
+    pick_executable(candidates)
+}
+
+fn from_zip(data: Vec<u8>) -> Result<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).context("zip 包格式错误")?;
+    let mut candidates = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).context("zip 包格式错误")?;
+        if !file.is_file() {
+            continue;
+        }
+        let path = file.name().to_string();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        if content.starts_with(ELF_MAGIC) {
+            candidates.push((path, content));
+        }
+    }
+    pick_executable(candidates)
+}
+
+/// .deb 是包含 data.tar.* 的 ar 包
+fn from_deb(data: &[u8]) -> Result<Vec<u8>> {
+    let mut archive = ar::Archive::new(data);
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry.context("deb 包格式错误")?;
+        let name = String::from_utf8_lossy(entry.header().identifier()).to_string();
+        if !name.starts_with("data.tar") {
+            continue;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        let tar = match name.as_str() {
+            "data.tar" => content,
+            "data.tar.gz" => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(content.as_slice()).read_to_end(&mut decoded).context("解压 data.tar.gz 失败")?;
+                decoded
+            }
+            "data.tar.xz" => {
+                let mut decoded = Vec::new();
+                lzma_rs::xz_decompress(&mut content.as_slice(), &mut decoded)
+                    .map_err(|e| anyhow!("解压 data.tar.xz 失败: {}", e))?;
+                decoded
+            }
+            _ => return Err(anyhow!("不支持 deb 包中的 {}，请先用 dpkg-deb -x 解包后指定目录", name)),
+        };
+        return from_tar(&tar);
+    }
+    Err(anyhow!("deb 包中没有 data.tar"))
+}
+
+/// 在解压后的目录中查找可执行文件
+///
+/// 跳过符号链接, 避免链接成环时无限遍历
+fn find_in_dir(dir: &Path) -> Result<Vec<u8>> {
+    let mut candidates = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).context(format!("读取目录 {} 失败", current.display()))? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_symlink() {
+                continue;
+            }
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let mut magic = [0u8; 4];
+            let is_elf = File::open(&path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && magic == ELF_MAGIC;
+            if is_elf {
+                let relative = path.strip_prefix(dir).unwrap_or(&path).display().to_string();
+                candidates.push((relative, fs::read(&path)?));
+            }
+        }
+    }
+    pick_executable(candidates)
+}
+
+/// 从安装包中的 ELF 文件里选出内核
+///
+/// 优先选择名为 mihomo 或 clash 的文件, 其次是以它们开头的文件
+fn pick_executable(candidates: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>> {
+    let rank = |path: &str| {
+        let name = Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        match name {
+            "mihomo" | "clash" => 0,
+            _ if name.starts_with("mihomo") || name.starts_with("clash") => 1,
+            _ => 2,
+        }
+    };
+    let Some(best) = candidates.iter().map(|(path, _)| rank(path)).min() else {
+        return Err(anyhow!("安装包中没有找到可执行文件"));
+    };
+    let mut matches: Vec<(String, Vec<u8>)> = candidates.into_iter().filter(|(path, _)| rank(path) == best).collect();
+    if matches.len() > 1 {
+        let names: Vec<&str> = matches.iter().map(|(path, _)| path.as_str()).collect();
+        return Err(anyhow!("安装包中有多个可执行文件，无法确定内核: {}", names.join(", ")));
+    }
+    Ok(matches.remove(0).1)
+}
+
+/// ELF 文件头中的机器类型、位数和字节序
+#[derive(PartialEq)]
+struct ElfTarget {
+    machine: u16,
+    is_64: bool,
+    little_endian: bool,
+}
+
+impl ElfTarget {
+    fn describe(&self) -> String {
+        let name = match self.machine {
+            3 => "x86",
+            8 => "MIPS",
+            21 => "PowerPC64",
+            22 => "s390x",
+            40 => "ARM",
+            62 => "x86_64",
+            183 => "AArch64",
+            243 => "RISC-V",
+            258 => "LoongArch",
+            other => return format!("未知机器类型 {}", other),
+        };
+        format!("{} ({} 位, {})", name, if self.is_64 { 64 } else { 32 }, if self.little_endian { "小端" } else { "大端" })
+    }
+}
+
+/// Mihomo 架构名对应的 ELF 目标, 未知架构返回 None
+fn expected_elf(arch: &str) -> Option<ElfTarget> {
+    let (machine, is_64, little_endian) = match arch {
+        a if a.starts_with("amd64") => (62, true, true),
+        "386" => (3, false, true),
+        "arm64" => (183, true, true),
+        a if a.starts_with("armv") => (40, false, true),
+        a if a.starts_with("mipsle-") => (8, false, true),
+        a if a.starts_with("mips-") => (8, false, false),
+        "mips64le" => (8, true, true),
+        "mips64" => (8, true, false),
+        "riscv64" => (243, true, true),
+        a if a.starts_with("loong64") => (258, true, true),
+        "s390x" => (22, true, false),
+        "ppc64le" => (21, true, true),
+        _ => return None,
+    };
+    Some(ElfTarget { machine, is_64, little_endian })
+}
+
+/// 检查可执行文件是否为指定架构的 ELF 文件, 避免安装无法运行的内核
+pub fn check_elf_arch(path: &Path, arch: &str) -> Result<()> {
+    let mut header = [0u8; 20];
+    File::open(path).and_then(|mut f| f.read_exact(&mut header))
+        .context("读取可执行文件失败")?;
+    if !header.starts_with(ELF_MAGIC) {
+        return Err(anyhow!("安装包中的文件不是 Linux 可执行文件 (ELF)"));
+    }
+    let little_endian = header[5] == 1;
+    let machine_bytes = [header[18], header[19]];
+    let actual = ElfTarget {
+        machine: if little_endian { u16::from_le_bytes(machine_bytes) } else { u16::from_be_bytes(machine_bytes) },
+        is_64: header[4] == 2,
+        little_endian,
+    };
+    let Some(expected) = expected_elf(arch) else { return Ok(()) };
+    if actual != expected {
+        return Err(anyhow!("可执行文件的架构为 {}，与 {} ({}) 不符，拒绝安装",
+            actual.describe(), arch, expected.describe()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(paths: &[&str]) -> Vec<(String, Vec<u8>)> {
+        paths.iter().map(|p| (p.to_string(), p.as_bytes().to_vec())).collect()
+    }
+
+    #[test]
+    fn picks_executable() {
+        let cases: [(&[&str], Option<&str>); 6] = [
+            (&["mihomo-linux-amd64"], Some("mihomo-linux-amd64")),
+            (&["LICENSE-helper", "usr/bin/mihomo", "mihomo-linux-amd64"], Some("usr/bin/mihomo")),
+            (&["tools/helper", "clash-meta"], Some("clash-meta")),
+            (&["bin/core"], Some("bin/core")),
+            (&["a/mihomo", "b/mihomo"], None),
+            (&[], None),
+        ];
+        for (paths, expected) in cases {
+            let picked = pick_executable(candidates(paths)).ok().map(|b| String::from_utf8(b).unwrap());
+            assert_eq!(picked.as_deref(), expected, "{:?}", paths);
+        }
+    }
+
+    /// 构造只有文件头的 ELF 文件
+    fn elf(is_64: bool, little_endian: bool, machine: u16) -> tempfile::NamedTempFile {
+        let mut header = [0u8; 20];
+        header[..4].copy_from_slice(ELF_MAGIC);
+        header[4] = if is_64 { 2 } else { 1 };
+        header[5] = if little_endian { 1 } else { 2 };
+        let machine = if little_endian { machine.to_le_bytes() } else { machine.to_be_bytes() };
+        header[18..20].copy_from_slice(&machine);
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), header).unwrap();
+        file
+    }
+
+    #[test]
+    fn checks_elf_arch() {
+        let cases = [
+            (elf(true, true, 62), "amd64-v3", true),
+            (elf(true, true, 62), "amd64-compatible", true),
+            (elf(true, true, 183), "amd64", false),
+            (elf(true, true, 183), "arm64", true),
+            (elf(false, true, 40), "armv7", true),
+            (elf(false, true, 40), "arm64", false),
+            (elf(false, false, 8), "mips-softfloat", true),
+            (elf(false, true, 8), "mips-softfloat", false),
+            (elf(true, true, 8), "mips64le", true),
+            (elf(false, true, 3), "386", true),
+            // 未知架构不做检查
+            (elf(true, true, 62), "unknown", true),
+        ];
+        for (file, arch, ok) in cases {
+            assert_eq!(check_elf_arch(file.path(), arch).is_ok(), ok, "{}", arch);
+        }
+
+        let script = tempfile::NamedTempFile::new().unwrap();
+        fs::write(script.path(), "#!/bin/sh\necho not an elf file\n").unwrap();
+        assert!(check_elf_arch(script.path(), "amd64").is_err());
+    }
+}
@@ -3,13 +3,68 @@ use std::io::copy;
 use std::path::Path;
 use anyhow::{Result, Context};
 use reqwest::Client;
-use flate2::read::GzDecoder;
 use futures_util::StreamExt;
 use std::io::Write;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// 下载响应中的元信息
+#[derive(Debug, Default)]
+pub struct DownloadInfo {
+    /// `ETag` 响应头
+    pub etag: Option<String>,
+    /// `subscription-userinfo` 响应头 (订阅流量与到期时间)
+    pub user_info: Option<SubscriptionUserInfo>,
+}
+
+/// 订阅流量信息
+///
+/// 格式: `upload=1234; download=5678; total=10737418240; expire=1700000000`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubscriptionUserInfo {
+    pub upload: u64,
+    pub download: u64,
+    pub total: u64,
+    /// 到期时间 (Unix 时间戳), 缺失或为 0 表示不限期
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expire: Option<i64>,
+}
+
+impl SubscriptionUserInfo {
+    /// 解析 `subscription-userinfo` 响应头, 忽略无法识别的字段
+    pub fn parse(header: &str) -> Option<Self> {
+        let mut info = SubscriptionUserInfo::default();
+        let mut found = false;
+        for part in header.split(';') {
+            let Some((key, value)) = part.split_once('=') else { continue };
+            // 部分机场返回浮点数
+            let Ok(value) = value.trim().parse::<f64>() else { continue };
+            match key.trim().to_lowercase().as_str() {
+                "upload" => info.upload = value as u64,
+                "download" => info.download = value as u64,
+                "total" => info.total = value as u64,
+                "expire" => info.expire = Some(value as i64).filter(|v| *v > 0),
+                _ => continue,
+            }
+            found = true;
+        }
+        found.then_some(info)
+    }
+
+    /// 已用流量
+    pub fn used(&self) -> u64 {
+        self.upload.saturating_add(self.download)
+    }
+
+    /// 剩余流量, total 为 0 时视为不限量
+    pub fn remaining(&self) -> Option<u64> {
+        (self.total > 0).then(|| self.total.saturating_sub(self.used()))
+    }
+}
 
 /// 下载文件并显示进度条
-pub async fn download_file(url: &str, target_path: &Path) -> Result<()> {
+pub async fn download_file(url: &str, target_path: &Path) -> Result<DownloadInfo> {
     // 使用自定义 User-Agent 模拟 Clash 客户端
     // 许多订阅服务会根据 UA 返回不同的格式 (Base64 vs YAML)
     let client = Client::builder()
@@ -17,7 +72,15 @@ pub async fn download_file(url: &str, target_path: &Path) -> Result<()> {
         .build()
         .context("Failed to build HTTP client")?;
         
-    let res = client.get(url).send().await.context("Failed to send request")?;
+    let res = client.get(url).send().await.context("Failed to send request")?
+        .error_for_status().context("Server returned an error status")?;
+    let header = |name: &str| res.headers().get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let info = DownloadInfo {
+        etag: header("etag"),
+        user_info: header("subscription-userinfo").and_then(|v| SubscriptionUserInfo::parse(&v)),
+    };
     let total_size = res.content_length().unwrap_or(0);
 
     // 设置进度条样式
@@ -37,26 +100,62 @@ pub async fn download_file(url: &str, target_path: &Path) -> Result<()> {
         pb.inc(chunk.len() as u64);
     }
     pb.finish_with_message("Download complete");
-    Ok(())
+    Ok(info)
 }
 
-/// 解压 .gz 文件
-/// 
-/// 自动为解压后的文件赋予 755 权限 (Unix)
-pub fn extract_gzip(archive_path: &Path, output_path: &Path) -> Result<()> {
-    let file = File::open(archive_path).context("Failed to open archive")?;
-    let mut decoder = GzDecoder::new(file);
-    let mut output = File::create(output_path).context("Failed to create output file")?;
-    copy(&mut decoder, &mut output).context("Failed to extract content")?;
-    
-    // chmod +x
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = output.metadata()?.permissions();
-        perms.set_mode(0o755);
-        output.set_permissions(perms)?;
+/// 检查下载地址是否存在 (HEAD 请求, 跟随重定向)
+pub async fn url_exists(url: &str) -> bool {
+    let Ok(client) = Client::builder().user_agent("clash-cli/0.1.0").build() else { return false };
+    client.head(url).send().await.is_ok_and(|res| res.status().is_success())
+}
+
+/// 计算文件的 SHA-256 (十六进制小写)
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).context("Failed to open file")?;
+    let mut hasher = Sha256::new();
+    copy(&mut file, &mut hasher).context("Failed to read file")?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subscription_userinfo() {
+        let gib = 1u64 << 30;
+        let cases = [
+            (
+                "upload=1024; download=2048; total=10737418240; expire=1700000000",
+                Some((1024, 2048, 10 * gib, Some(1700000000))),
+            ),
+            // 浮点数、大小写、多余空格和未知字段
+            (" Upload = 1.5e3 ;DOWNLOAD=20.9; total=0; plan=pro; expire=", Some((1500, 20, 0, None))),
+            ("download=1; expire=0", Some((0, 1, 0, None))),
+            ("upload=-5; download=1e30", Some((0, u64::MAX, 0, None))),
+            ("plan=pro; upload=abc", None),
+            ("", None),
+        ];
+        for (header, expected) in cases {
+            let info = SubscriptionUserInfo::parse(header).map(|i| (i.upload, i.download, i.total, i.expire));
+            assert_eq!(info, expected, "{}", header);
+        }
+    }
+
+    #[test]
+    fn computes_used_and_remaining() {
+        let info = |upload, download, total| SubscriptionUserInfo { upload, download, total, expire: None };
+        let cases = [
+            (info(100, 200, 1000), 300, Some(700)),
+            (info(600, 600, 1000), 1200, Some(0)),
+            (info(100, 200, 0), 300, None),
+            // 超大计数器不能溢出
+            (info(u64::MAX, 1, u64::MAX), u64::MAX, Some(0)),
+            (info(u64::MAX, u64::MAX, 1000), u64::MAX, Some(0)),
+        ];
+        for (info, used, remaining) in cases {
+            assert_eq!(info.used(), used, "{:?}", info);
+            assert_eq!(info.remaining(), remaining, "{:?}", info);
+        }
     }
-    
-    Ok(())
 }
@@ -0,0 +1,38 @@
+use console::{pad_str, Alignment};
+
+/// 格式化字节数 (B/KB/MB/GB)
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{:.2} GB", bytes as f64 / 1024.0 / 1024.0 / 1024.0)
+    }
+}
+
+/// 格式化时长, 如 "45s"、"3m12s"、"2h05m"
+pub fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else if secs < 86400 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}d{:02}h", secs / 86400, secs % 86400 / 3600)
+    }
+}
+
+/// 按显示宽度左对齐填充 (正确处理中文宽字符和颜色控制符)
+pub fn pad(text: &str, width: usize) -> String {
+    pad_str(text, width, Alignment::Left, None).into_owned()
+}
+
+/// 按显示宽度右对齐填充
+pub fn pad_left(text: &str, width: usize) -> String {
+    pad_str(text, width, Alignment::Right, None).into_owned()
+}
@@ -1,2 +1,4 @@
+pub mod archive;
 pub mod download;
 pub mod system;
+pub mod format;
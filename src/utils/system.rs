@@ -1,16 +1,140 @@
 use std::env;
+use std::fs;
+use std::path::Path;
 use anyhow::{Result, anyhow};
 
-/// 获取系统架构 (amd64/arm64)
-pub fn get_arch() -> Result<&'static str> {
-    match env::consts::ARCH {
-        "x86_64" => Ok("amd64"),
-        "aarch64" => Ok("arm64"),
-        "arm" => Ok("armv7"), // Simplified, might need more check for armv6/v7
-        _ => Err(anyhow!("Unsupported architecture: {}", env::consts::ARCH)),
+/// 检测到的 CPU 架构
+pub struct ArchInfo {
+    /// 可用的 Mihomo 安装包架构名, 按优先级排列
+    pub candidates: Vec<String>,
+    /// 检测依据, 如 "x86_64, x86-64-v3"
+    pub detail: String,
+}
+
+impl ArchInfo {
+    fn new(candidates: &[&str], detail: String) -> Self {
+        ArchInfo { candidates: candidates.iter().map(|c| c.to_string()).collect(), detail }
+    }
+
+    /// 使用 --arch 指定的架构, 不做检测
+    pub fn from_override(arch: &str) -> Self {
+        ArchInfo { candidates: vec![arch.to_string()], detail: "--arch 指定".to_string() }
+    }
+
+    /// 首选架构名
+    pub fn primary(&self) -> &str {
+        &self.candidates[0]
+    }
+}
+
+/// 检测系统架构, 返回与 Mihomo 安装包命名一致的架构名
+///
+/// x86_64 根据 CPU 指令集选择 amd64-v1/v2/v3, 32 位 ARM 根据架构版本和 VFP 浮点单元选择 armv5/6/7,
+/// MIPS 根据字节序和是否有 FPU 选择 softfloat/hardfloat 版本
+pub fn detect_arch() -> Result<ArchInfo> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    let arch = env::consts::ARCH;
+    let info = match arch {
+        "x86_64" => {
+            let level = x86_64_level(&cpuinfo);
+            let detail = format!("{}, x86-64-v{}", arch, level);
+            // 旧版本只提供 amd64 (v3) 和 amd64-compatible (v1)
+            match level {
+                3 => ArchInfo::new(&["amd64-v3", "amd64"], detail),
+                2 => ArchInfo::new(&["amd64-v2", "amd64-v1", "amd64-compatible"], detail),
+                _ => ArchInfo::new(&["amd64-v1", "amd64-compatible"], detail),
+            }
+        }
+        "x86" => ArchInfo::new(&["386"], arch.to_string()),
+        "aarch64" => ArchInfo::new(&["arm64"], arch.to_string()),
+        "arm" => {
+            let version = cpuinfo_field(&cpuinfo, "CPU architecture")
+                .and_then(|v| v.trim_end_matches(|c: char| !c.is_ascii_digit()).parse::<u32>().ok())
+                .unwrap_or(7);
+            let vfp = arm_vfp_version(&cpuinfo);
+            let detail = match vfp {
+                Some(v) => format!("{}, ARMv{}, VFPv{}", arch, version, v),
+                None => format!("{}, ARMv{}, 无 VFP (软浮点)", arch, version),
+            };
+            // armv6/armv7 使用 VFP 硬浮点指令, armv5 为软浮点版本, 可在任何 ARM 上运行
+            match (version, vfp) {
+                (7.., Some(3..)) => ArchInfo::new(&["armv7", "armv6", "armv5"], detail),
+                (6.., Some(_)) => ArchInfo::new(&["armv6", "armv5"], detail),
+                _ => ArchInfo::new(&["armv5"], detail),
+            }
+        }
+        "mips" | "mips64" => {
+            let little = cfg!(target_endian = "little");
+            let endian = if little { "little-endian" } else { "big-endian" };
+            if arch == "mips64" {
+                let name = if little { "mips64le" } else { "mips64" };
+                ArchInfo::new(&[name], format!("{}, {}", arch, endian))
+            } else {
+                // 大多数路由器没有 FPU, 软浮点版本同样可以在有 FPU 的设备上运行
+                let has_fpu = cpuinfo_field(&cpuinfo, "cpu model").is_some_and(|m| m.contains("FPU"));
+                let detail = format!("{}, {}, {}", arch, endian, if has_fpu { "FPU" } else { "无 FPU" });
+                match (little, has_fpu) {
+                    (true, true) => ArchInfo::new(&["mipsle-hardfloat", "mipsle-softfloat"], detail),
+                    (true, false) => ArchInfo::new(&["mipsle-softfloat"], detail),
+                    (false, true) => ArchInfo::new(&["mips-hardfloat", "mips-softfloat"], detail),
+                    (false, false) => ArchInfo::new(&["mips-softfloat"], detail),
+                }
+            }
+        }
+        "riscv64" => ArchInfo::new(&["riscv64"], arch.to_string()),
+        "loongarch64" => {
+            // 新世界 (ABI2) 与旧世界 (ABI1) 使用不同的动态链接器
+            if Path::new("/lib64/ld-linux-loongarch-lp64d.so.1").exists() {
+                ArchInfo::new(&["loong64-abi2", "loong64"], format!("{}, ABI2", arch))
+            } else {
+                ArchInfo::new(&["loong64-abi1", "loong64"], format!("{}, ABI1", arch))
+            }
+        }
+        "s390x" => ArchInfo::new(&["s390x"], arch.to_string()),
+        "powerpc64" if cfg!(target_endian = "little") => ArchInfo::new(&["ppc64le"], arch.to_string()),
+        _ => return Err(anyhow!("Unsupported architecture: {}，可通过 --arch 指定", arch)),
+    };
+    Ok(info)
+}
+
+/// 读取 /proc/cpuinfo 中第一个匹配字段的值
+fn cpuinfo_field<'a>(cpuinfo: &'a str, key: &str) -> Option<&'a str> {
+    cpuinfo.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        (k.trim() == key).then(|| v.trim())
+    })
+}
+
+/// 根据 CPU 标志判断 x86-64 微架构级别 (1-3)
+fn x86_64_level(cpuinfo: &str) -> u8 {
+    let flags: Vec<&str> = cpuinfo_field(cpuinfo, "flags").unwrap_or_default().split_whitespace().collect();
+    let has_all = |required: &[&str]| required.iter().all(|f| flags.contains(f));
+    // pni 即 SSE3, abm 包含 LZCNT
+    let v2 = has_all(&["cx16", "lahf_lm", "popcnt", "pni", "sse4_1", "sse4_2", "ssse3"]);
+    let v3 = has_all(&["avx", "avx2", "bmi1", "bmi2", "f16c", "fma", "abm", "movbe", "xsave"]);
+    match (v2, v3) {
+        (true, true) => 3,
+        (true, false) => 2,
+        _ => 1,
     }
 }
 
+/// 32 位 ARM 支持的 VFP 版本, 取 Features 中 vfp、vfpv3、vfpv4 等标志的最高版本
+fn arm_vfp_version(cpuinfo: &str) -> Option<u32> {
+    cpuinfo_field(cpuinfo, "Features")?
+        .split_whitespace()
+        .filter_map(|feature| {
+            let rest = feature.strip_prefix("vfp")?;
+            if rest.is_empty() {
+                return Some(2);
+            }
+            // 如 vfpv3d16
+            let digits: String = rest.strip_prefix('v')?.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .max()
+}
+
 /// 获取操作系统 (linux/darwin)
 pub fn get_os() -> Result<&'static str> {
     match env::consts::OS {
@@ -19,3 +143,42 @@ pub fn get_os() -> Result<&'static str> {
         _ => Err(anyhow!("Unsupported OS: {}", env::consts::OS)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V2_FLAGS: &str = "fpu sse sse2 cx16 lahf_lm popcnt pni sse4_1 sse4_2 ssse3";
+    const V3_FLAGS: &str = "avx avx2 bmi1 bmi2 f16c fma abm movbe xsave";
+
+    #[test]
+    fn detects_x86_64_level() {
+        let cases = [
+            (format!("processor\t: 0\nflags\t\t: {} {}\n", V2_FLAGS, V3_FLAGS), 3),
+            (format!("flags\t\t: {}\n", V2_FLAGS), 2),
+            // 缺少 movbe 时只有 v2
+            (format!("flags\t\t: {} {}\n", V2_FLAGS, V3_FLAGS.replace("movbe", "")), 2),
+            // 有 AVX2 但缺少 v2 要求的 popcnt
+            (format!("flags\t\t: {} {}\n", V2_FLAGS.replace("popcnt", ""), V3_FLAGS), 1),
+            ("flags\t\t: fpu sse sse2\n".to_string(), 1),
+            (String::new(), 1),
+        ];
+        for (cpuinfo, expected) in cases {
+            assert_eq!(x86_64_level(&cpuinfo), expected, "{}", cpuinfo);
+        }
+    }
+
+    #[test]
+    fn detects_arm_vfp_version() {
+        let cases = [
+            ("Features\t: half thumb fastmult vfp edsp neon vfpv3 tls vfpv4 idiva idivt vfpd32 lpae", Some(4)),
+            ("Features\t: swp half thumb fastmult vfp edsp vfpv3d16", Some(3)),
+            ("Features\t: swp half thumb fastmult vfp edsp java", Some(2)),
+            ("Features\t: swp half thumb fastmult edsp", None),
+            ("processor\t: 0", None),
+        ];
+        for (cpuinfo, expected) in cases {
+            assert_eq!(arm_vfp_version(cpuinfo), expected, "{}", cpuinfo);
+        }
+    }
+}